@@ -0,0 +1,915 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_data::{check_type, expect_length, invalid, read_u16, read_u32};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::ihdr::{ColorType, Ihdr};
+use crate::palette::Palette;
+use crate::png::Png;
+use crate::text::{check_keyword, decode_latin1, encode_latin1};
+use crate::validate::Diagnostic;
+
+const METRES_PER_INCH: f64 = 0.0254;
+
+/// Unit of the `pHYs` pixel density.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PhysicalUnit {
+    /// Only the aspect ratio is known.
+    Unknown = 0,
+    Metre = 1,
+}
+
+/// The decoded contents of the `pHYs` chunk: pixels per unit on each axis.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PhysicalDimensions {
+    pub pixels_per_unit_x: u32,
+    pub pixels_per_unit_y: u32,
+    pub unit: PhysicalUnit,
+}
+
+impl PhysicalDimensions {
+    pub const LENGTH: usize = 9;
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("pHYs").unwrap()
+    }
+
+    /// Square pixels at `dpi` dots per inch, stored as pixels per metre.
+    pub fn from_dpi(dpi: u32) -> PhysicalDimensions {
+        let pixels_per_metre = (dpi as f64 / METRES_PER_INCH).round() as u32;
+        PhysicalDimensions {
+            pixels_per_unit_x: pixels_per_metre,
+            pixels_per_unit_y: pixels_per_metre,
+            unit: PhysicalUnit::Metre,
+        }
+    }
+
+    /// Dots per inch on each axis, if the unit is known.
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        match self.unit {
+            PhysicalUnit::Metre => Some((
+                self.pixels_per_unit_x as f64 * METRES_PER_INCH,
+                self.pixels_per_unit_y as f64 * METRES_PER_INCH,
+            )),
+            PhysicalUnit::Unknown => None,
+        }
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::with_capacity(PhysicalDimensions::LENGTH);
+        data.extend_from_slice(&self.pixels_per_unit_x.to_be_bytes());
+        data.extend_from_slice(&self.pixels_per_unit_y.to_be_bytes());
+        data.push(self.unit as u8);
+        Chunk::new(PhysicalDimensions::chunk_type(), data)
+    }
+}
+
+impl TryFrom<&Chunk> for PhysicalDimensions {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, PhysicalDimensions::chunk_type())?;
+        expect_length(chunk, PhysicalDimensions::LENGTH)?;
+        let data = chunk.data();
+        let unit = match data[8] {
+            0 => PhysicalUnit::Unknown,
+            1 => PhysicalUnit::Metre,
+            other => {
+                return Err(invalid(
+                    PhysicalDimensions::chunk_type(),
+                    format!("unknown unit {}", other),
+                ))
+            }
+        };
+        Ok(PhysicalDimensions {
+            pixels_per_unit_x: read_u32(data, 0),
+            pixels_per_unit_y: read_u32(data, 4),
+            unit,
+        })
+    }
+}
+
+impl fmt::Display for PhysicalDimensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.dpi() {
+            Some((x, y)) => write!(
+                f,
+                "{} x {} pixels per metre ({:.0} x {:.0} dpi)",
+                self.pixels_per_unit_x, self.pixels_per_unit_y, x, y
+            ),
+            None => write!(
+                f,
+                "aspect ratio {}:{} (unit unknown)",
+                self.pixels_per_unit_x, self.pixels_per_unit_y
+            ),
+        }
+    }
+}
+
+/// The decoded contents of the `sBIT` chunk: how many bits of each channel
+/// were significant in the original data, in channel order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignificantBits {
+    pub bits: Vec<u8>,
+}
+
+impl SignificantBits {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("sBIT").unwrap()
+    }
+
+    /// Decodes `chunk` for an image described by `ihdr`, which sets the
+    /// number of channels and the largest allowed value.
+    pub fn from_chunk(chunk: &Chunk, ihdr: &Ihdr) -> Result<Self> {
+        check_type(chunk, SignificantBits::chunk_type())?;
+        let channels = match ihdr.color_type {
+            ColorType::Grayscale => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb | ColorType::Indexed => 3,
+            ColorType::Rgba => 4,
+        };
+        expect_length(chunk, channels)?;
+        let max = match ihdr.color_type {
+            ColorType::Indexed => 8,
+            _ => ihdr.bit_depth,
+        };
+        if let Some(&bits) = chunk.data().iter().find(|&&b| b == 0 || b > max) {
+            return Err(invalid(
+                SignificantBits::chunk_type(),
+                format!("{} significant bits is out of range 1 to {}", bits, max),
+            ));
+        }
+        Ok(SignificantBits {
+            bits: chunk.data().to_vec(),
+        })
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(SignificantBits::chunk_type(), self.bits.clone())
+    }
+}
+
+impl fmt::Display for SignificantBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits: Vec<String> = self.bits.iter().map(u8::to_string).collect();
+        write!(f, "{}", bits.join(", "))
+    }
+}
+
+/// The decoded contents of the `bKGD` chunk, whose layout depends on the colour type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Background {
+    PaletteIndex(u8),
+    Gray(u16),
+    Rgb { red: u16, green: u16, blue: u16 },
+}
+
+impl Background {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("bKGD").unwrap()
+    }
+
+    /// Decodes `chunk` for an image described by `ihdr`. Indexed images need
+    /// their palette to check the index.
+    pub fn from_chunk(chunk: &Chunk, ihdr: &Ihdr, palette: Option<&Palette>) -> Result<Self> {
+        check_type(chunk, Background::chunk_type())?;
+        let data = chunk.data();
+        match ihdr.color_type {
+            ColorType::Indexed => {
+                expect_length(chunk, 1)?;
+                let entries = palette.map_or(0, |palette| palette.entries.len());
+                if data[0] as usize >= entries {
+                    return Err(invalid(
+                        Background::chunk_type(),
+                        format!(
+                            "palette index {} is out of range for {} entries",
+                            data[0], entries
+                        ),
+                    ));
+                }
+                Ok(Background::PaletteIndex(data[0]))
+            }
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => {
+                expect_length(chunk, 2)?;
+                Ok(Background::Gray(read_u16(data, 0)))
+            }
+            ColorType::Rgb | ColorType::Rgba => {
+                expect_length(chunk, 6)?;
+                Ok(Background::Rgb {
+                    red: read_u16(data, 0),
+                    green: read_u16(data, 2),
+                    blue: read_u16(data, 4),
+                })
+            }
+        }
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = match self {
+            Background::PaletteIndex(index) => vec![*index],
+            Background::Gray(gray) => gray.to_be_bytes().to_vec(),
+            Background::Rgb { red, green, blue } => [red, green, blue]
+                .iter()
+                .flat_map(|sample| sample.to_be_bytes())
+                .collect(),
+        };
+        Chunk::new(Background::chunk_type(), data)
+    }
+}
+
+impl fmt::Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Background::PaletteIndex(index) => write!(f, "palette index {}", index),
+            Background::Gray(gray) => write!(f, "gray {}", gray),
+            Background::Rgb { red, green, blue } => {
+                write!(f, "RGB ({}, {}, {})", red, green, blue)
+            }
+        }
+    }
+}
+
+/// The decoded contents of the `hIST` chunk: approximate usage of each palette entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    pub frequencies: Vec<u16>,
+}
+
+impl Histogram {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("hIST").unwrap()
+    }
+
+    /// Checks there is exactly one frequency per palette entry.
+    pub fn validate(&self, palette: &Palette) -> Result<()> {
+        if self.frequencies.len() != palette.entries.len() {
+            return Err(invalid(
+                Histogram::chunk_type(),
+                format!(
+                    "{} frequencies for a palette of {} entries",
+                    self.frequencies.len(),
+                    palette.entries.len()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = self
+            .frequencies
+            .iter()
+            .flat_map(|frequency| frequency.to_be_bytes())
+            .collect();
+        Chunk::new(Histogram::chunk_type(), data)
+    }
+}
+
+impl TryFrom<&Chunk> for Histogram {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Histogram::chunk_type())?;
+        let data = chunk.data();
+        if !data.len().is_multiple_of(2) {
+            return Err(invalid(
+                Histogram::chunk_type(),
+                format!("length {} is not a multiple of 2", data.len()),
+            ));
+        }
+        Ok(Histogram {
+            frequencies: data.chunks_exact(2).map(|pair| read_u16(pair, 0)).collect(),
+        })
+    }
+}
+
+/// One colour of an `sPLT` suggested palette. Samples use the palette's sample depth.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SuggestedPaletteEntry {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    pub alpha: u16,
+    pub frequency: u16,
+}
+
+/// The decoded contents of an `sPLT` chunk: a named palette suggested for
+/// displays that cannot show the full colour range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestedPalette {
+    pub name: String,
+    /// 8 or 16.
+    pub sample_depth: u8,
+    pub entries: Vec<SuggestedPaletteEntry>,
+}
+
+impl SuggestedPalette {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("sPLT").unwrap()
+    }
+
+    fn entry_size(sample_depth: u8) -> Result<usize> {
+        match sample_depth {
+            8 => Ok(6),
+            16 => Ok(10),
+            other => Err(invalid(
+                SuggestedPalette::chunk_type(),
+                format!("sample depth {} is not 8 or 16", other),
+            )),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let chunk_type = SuggestedPalette::chunk_type();
+        check_keyword(chunk_type, &self.name)?;
+        SuggestedPalette::entry_size(self.sample_depth)?;
+        let mut data = encode_latin1(chunk_type, &self.name)?;
+        data.push(0);
+        data.push(self.sample_depth);
+        for entry in &self.entries {
+            for sample in [entry.red, entry.green, entry.blue, entry.alpha] {
+                if self.sample_depth == 8 {
+                    data.push(sample as u8);
+                } else {
+                    data.extend_from_slice(&sample.to_be_bytes());
+                }
+            }
+            data.extend_from_slice(&entry.frequency.to_be_bytes());
+        }
+        Ok(Chunk::new(chunk_type, data))
+    }
+}
+
+impl TryFrom<&Chunk> for SuggestedPalette {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let chunk_type = SuggestedPalette::chunk_type();
+        check_type(chunk, chunk_type)?;
+        let data = chunk.data();
+        let separator = data.iter().position(|&b| b == 0).ok_or_else(|| {
+            invalid(
+                chunk_type,
+                "missing null separator after palette name".to_string(),
+            )
+        })?;
+        let name = decode_latin1(&data[..separator]);
+        check_keyword(chunk_type, &name)?;
+        let sample_depth = *data
+            .get(separator + 1)
+            .ok_or_else(|| invalid(chunk_type, "missing sample depth".to_string()))?;
+        let size = SuggestedPalette::entry_size(sample_depth)?;
+        let rest = &data[separator + 2..];
+        if !rest.len().is_multiple_of(size) {
+            return Err(invalid(
+                chunk_type,
+                format!(
+                    "{} bytes of entries is not a multiple of {}",
+                    rest.len(),
+                    size
+                ),
+            ));
+        }
+
+        let entries = rest
+            .chunks_exact(size)
+            .map(|entry| {
+                let sample = |i: usize| {
+                    if sample_depth == 8 {
+                        entry[i] as u16
+                    } else {
+                        read_u16(entry, i * 2)
+                    }
+                };
+                SuggestedPaletteEntry {
+                    red: sample(0),
+                    green: sample(1),
+                    blue: sample(2),
+                    alpha: sample(3),
+                    frequency: read_u16(entry, size - 2),
+                }
+            })
+            .collect();
+        Ok(SuggestedPalette {
+            name,
+            sample_depth,
+            entries,
+        })
+    }
+}
+
+impl fmt::Display for SuggestedPalette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} entries, {}-bit samples)",
+            self.name,
+            self.entries.len(),
+            self.sample_depth
+        )
+    }
+}
+
+/// Unit of the `oFFs` image position.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OffsetUnit {
+    Pixel = 0,
+    Micrometre = 1,
+}
+
+/// The decoded contents of the `oFFs` chunk: where the image sits on a page.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageOffset {
+    pub x: i32,
+    pub y: i32,
+    pub unit: OffsetUnit,
+}
+
+impl ImageOffset {
+    pub const LENGTH: usize = 9;
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("oFFs").unwrap()
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::with_capacity(ImageOffset::LENGTH);
+        data.extend_from_slice(&self.x.to_be_bytes());
+        data.extend_from_slice(&self.y.to_be_bytes());
+        data.push(self.unit as u8);
+        Chunk::new(ImageOffset::chunk_type(), data)
+    }
+}
+
+impl TryFrom<&Chunk> for ImageOffset {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, ImageOffset::chunk_type())?;
+        expect_length(chunk, ImageOffset::LENGTH)?;
+        let data = chunk.data();
+        let unit = match data[8] {
+            0 => OffsetUnit::Pixel,
+            1 => OffsetUnit::Micrometre,
+            other => {
+                return Err(invalid(
+                    ImageOffset::chunk_type(),
+                    format!("unknown unit {}", other),
+                ))
+            }
+        };
+        Ok(ImageOffset {
+            x: read_u32(data, 0) as i32,
+            y: read_u32(data, 4) as i32,
+            unit,
+        })
+    }
+}
+
+impl fmt::Display for ImageOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            OffsetUnit::Pixel => "pixels",
+            OffsetUnit::Micrometre => "micrometres",
+        };
+        write!(f, "({}, {}) {}", self.x, self.y, unit)
+    }
+}
+
+/// Unit of the `sCAL` pixel size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScaleUnit {
+    Metre = 1,
+    Radian = 2,
+}
+
+/// The decoded contents of the `sCAL` chunk: the physical size of one pixel.
+/// Sizes are kept as the ASCII floating point strings the chunk stores.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhysicalScale {
+    pub unit: ScaleUnit,
+    pub pixel_width: String,
+    pub pixel_height: String,
+}
+
+impl PhysicalScale {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("sCAL").unwrap()
+    }
+
+    /// Checks both sizes are positive, finite ASCII floating point numbers.
+    /// `f64::from_str` also accepts "inf" and "infinity", which sCAL does not.
+    pub fn validate(&self) -> Result<()> {
+        for size in [&self.pixel_width, &self.pixel_height] {
+            let valid = size.is_ascii()
+                && !size.starts_with(['+', '-'])
+                && size
+                    .parse::<f64>()
+                    .is_ok_and(|value| value.is_finite() && value > 0.0);
+            if !valid {
+                return Err(invalid(
+                    PhysicalScale::chunk_type(),
+                    format!("{:?} is not a positive finite number", size),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.validate()?;
+        let mut data = vec![self.unit as u8];
+        data.extend_from_slice(self.pixel_width.as_bytes());
+        data.push(0);
+        data.extend_from_slice(self.pixel_height.as_bytes());
+        Ok(Chunk::new(PhysicalScale::chunk_type(), data))
+    }
+}
+
+impl TryFrom<&Chunk> for PhysicalScale {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let chunk_type = PhysicalScale::chunk_type();
+        check_type(chunk, chunk_type)?;
+        let data = chunk.data();
+        let unit = match data.first() {
+            Some(1) => ScaleUnit::Metre,
+            Some(2) => ScaleUnit::Radian,
+            other => return Err(invalid(chunk_type, format!("unknown unit {:?}", other))),
+        };
+        let rest = &data[1..];
+        let separator = rest.iter().position(|&b| b == 0).ok_or_else(|| {
+            invalid(
+                chunk_type,
+                "missing null separator after pixel width".to_string(),
+            )
+        })?;
+        let scale = PhysicalScale {
+            unit,
+            pixel_width: decode_latin1(&rest[..separator]),
+            pixel_height: decode_latin1(&rest[separator + 1..]),
+        };
+        scale.validate()?;
+        Ok(scale)
+    }
+}
+
+impl fmt::Display for PhysicalScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            ScaleUnit::Metre => "metres",
+            ScaleUnit::Radian => "radians",
+        };
+        write!(
+            f,
+            "{} x {} {} per pixel",
+            self.pixel_width, self.pixel_height, unit
+        )
+    }
+}
+
+/// Every registered ancillary chunk describing the image that is not
+/// covered by `ColorInfo`, text or `tIME`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AncillaryInfo {
+    pub physical_dimensions: Option<PhysicalDimensions>,
+    pub significant_bits: Option<SignificantBits>,
+    pub background: Option<Background>,
+    pub histogram: Option<Histogram>,
+    pub suggested_palettes: Vec<SuggestedPalette>,
+    pub offset: Option<ImageOffset>,
+    pub scale: Option<PhysicalScale>,
+    /// One entry per chunk that could not be decoded and was left out.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for AncillaryInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(physical_dimensions) = &self.physical_dimensions {
+            writeln!(f, "physical dimensions: {}", physical_dimensions)?;
+        }
+        if let Some(significant_bits) = &self.significant_bits {
+            writeln!(f, "significant bits: {}", significant_bits)?;
+        }
+        if let Some(background) = &self.background {
+            writeln!(f, "background: {}", background)?;
+        }
+        if let Some(histogram) = &self.histogram {
+            writeln!(f, "histogram: {} entries", histogram.frequencies.len())?;
+        }
+        for palette in &self.suggested_palettes {
+            writeln!(f, "suggested palette: {}", palette)?;
+        }
+        if let Some(offset) = &self.offset {
+            writeln!(f, "offset: {}", offset)?;
+        }
+        if let Some(scale) = &self.scale {
+            writeln!(f, "scale: {}", scale)?;
+        }
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic)?;
+        }
+        Ok(())
+    }
+}
+
+impl Png {
+    /// Decodes the `pHYs`, `sBIT`, `bKGD`, `hIST`, `sPLT`, `oFFs` and `sCAL`
+    /// chunks that are present, checking them against the header and palette.
+    /// A chunk that fails to decode is skipped and reported in `diagnostics`.
+    pub fn ancillary_info(&self) -> AncillaryInfo {
+        let mut info = AncillaryInfo::default();
+        for (index, chunk) in self.chunks().iter().enumerate() {
+            let decoded = match chunk.chunk_type().to_string().as_str() {
+                "pHYs" if info.physical_dimensions.is_none() => PhysicalDimensions::try_from(chunk)
+                    .map(|value| info.physical_dimensions = Some(value)),
+                "oFFs" if info.offset.is_none() => {
+                    ImageOffset::try_from(chunk).map(|value| info.offset = Some(value))
+                }
+                "sCAL" if info.scale.is_none() => {
+                    PhysicalScale::try_from(chunk).map(|value| info.scale = Some(value))
+                }
+                "sPLT" => SuggestedPalette::try_from(chunk)
+                    .map(|value| info.suggested_palettes.push(value)),
+                "sBIT" if info.significant_bits.is_none() => self
+                    .header_info()
+                    .and_then(|header| SignificantBits::from_chunk(chunk, &header))
+                    .map(|value| info.significant_bits = Some(value)),
+                "bKGD" if info.background.is_none() => self
+                    .header_info()
+                    .and_then(|header| {
+                        Background::from_chunk(chunk, &header, self.palette()?.as_ref())
+                    })
+                    .map(|value| info.background = Some(value)),
+                "hIST" if info.histogram.is_none() => self
+                    .histogram(chunk)
+                    .map(|value| info.histogram = Some(value)),
+                _ => Ok(()),
+            };
+            if let Err(error) = decoded {
+                info.diagnostics
+                    .push(Diagnostic::error(Some(index), error.to_string()));
+            }
+        }
+        info
+    }
+
+    fn histogram(&self, chunk: &Chunk) -> Result<Histogram> {
+        let histogram = Histogram::try_from(chunk)?;
+        let palette = self
+            .palette()?
+            .ok_or_else(|| PngError::ChunkNotFound("PLTE".to_string()))?;
+        histogram.validate(&palette)?;
+        Ok(histogram)
+    }
+
+    pub fn physical_dimensions(&self) -> Result<Option<PhysicalDimensions>> {
+        self.chunk_by_type("pHYs")
+            .map(PhysicalDimensions::try_from)
+            .transpose()
+    }
+
+    /// Replaces the `pHYs` chunk, adding one before the image data if missing.
+    pub fn set_physical_dimensions(&mut self, dimensions: PhysicalDimensions) {
+        self.remove_chunks_where(|chunk| chunk.chunk_type() == PhysicalDimensions::chunk_type());
+        self.insert_before_image_data(dimensions.to_chunk());
+    }
+
+    /// Marks the image as `dpi` dots per inch with square pixels.
+    pub fn set_dpi(&mut self, dpi: u32) {
+        self.set_physical_dimensions(PhysicalDimensions::from_dpi(dpi));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ihdr::InterlaceMethod;
+    use crate::palette::PaletteEntry;
+
+    fn ihdr(color_type: ColorType, bit_depth: u8) -> Ihdr {
+        Ihdr {
+            width: 1,
+            height: 1,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+    }
+
+    fn testing_palette(len: usize) -> Palette {
+        Palette {
+            entries: vec![
+                PaletteEntry {
+                    red: 1,
+                    green: 2,
+                    blue: 3
+                };
+                len
+            ],
+        }
+    }
+
+    #[test]
+    fn test_physical_dimensions() {
+        let dims = PhysicalDimensions::from_dpi(300);
+        assert_eq!(dims.pixels_per_unit_x, 11811);
+        let chunk = dims.to_chunk();
+        assert_eq!(PhysicalDimensions::try_from(&chunk).unwrap(), dims);
+        let (x, y) = dims.dpi().unwrap();
+        assert_eq!((x.round(), y.round()), (300.0, 300.0));
+        assert_eq!(
+            dims.to_string(),
+            "11811 x 11811 pixels per metre (300 x 300 dpi)"
+        );
+
+        let chunk = Chunk::new(
+            PhysicalDimensions::chunk_type(),
+            vec![0, 0, 0, 1, 0, 0, 0, 2, 0],
+        );
+        let dims = PhysicalDimensions::try_from(&chunk).unwrap();
+        assert_eq!(dims.dpi(), None);
+        let chunk = Chunk::new(
+            PhysicalDimensions::chunk_type(),
+            vec![0, 0, 0, 1, 0, 0, 0, 2, 2],
+        );
+        assert!(PhysicalDimensions::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_significant_bits() {
+        let chunk = Chunk::new(SignificantBits::chunk_type(), vec![5, 6, 5]);
+        let sbit = SignificantBits::from_chunk(&chunk, &ihdr(ColorType::Rgb, 8)).unwrap();
+        assert_eq!(sbit.bits, [5, 6, 5]);
+        assert_eq!(sbit.to_chunk().data(), chunk.data());
+        assert!(SignificantBits::from_chunk(&chunk, &ihdr(ColorType::Rgba, 8)).is_err());
+        assert!(SignificantBits::from_chunk(&chunk, &ihdr(ColorType::Indexed, 1)).is_ok());
+
+        let chunk = Chunk::new(SignificantBits::chunk_type(), vec![3]);
+        assert!(SignificantBits::from_chunk(&chunk, &ihdr(ColorType::Grayscale, 2)).is_err());
+    }
+
+    #[test]
+    fn test_background() {
+        let chunk = Chunk::new(Background::chunk_type(), vec![2]);
+        let indexed = ihdr(ColorType::Indexed, 8);
+        assert_eq!(
+            Background::from_chunk(&chunk, &indexed, Some(&testing_palette(3))).unwrap(),
+            Background::PaletteIndex(2)
+        );
+        assert!(Background::from_chunk(&chunk, &indexed, Some(&testing_palette(2))).is_err());
+
+        let background = Background::Rgb {
+            red: 1,
+            green: 2,
+            blue: 3,
+        };
+        let chunk = background.to_chunk();
+        assert_eq!(
+            Background::from_chunk(&chunk, &ihdr(ColorType::Rgba, 16), None).unwrap(),
+            background
+        );
+        assert!(Background::from_chunk(&chunk, &ihdr(ColorType::Grayscale, 8), None).is_err());
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram {
+            frequencies: vec![1, 300],
+        };
+        let chunk = histogram.to_chunk();
+        assert_eq!(chunk.data(), [0, 1, 1, 44]);
+        assert_eq!(Histogram::try_from(&chunk).unwrap(), histogram);
+        assert!(histogram.validate(&testing_palette(2)).is_ok());
+        assert!(histogram.validate(&testing_palette(3)).is_err());
+        let chunk = Chunk::new(Histogram::chunk_type(), vec![0; 3]);
+        assert!(Histogram::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_suggested_palette() {
+        for sample_depth in [8, 16] {
+            let palette = SuggestedPalette {
+                name: "web safe".to_string(),
+                sample_depth,
+                entries: vec![SuggestedPaletteEntry {
+                    red: 255,
+                    green: 0,
+                    blue: 51,
+                    alpha: 255,
+                    frequency: 7,
+                }],
+            };
+            let chunk = palette.to_chunk().unwrap();
+            assert_eq!(
+                chunk.data().len(),
+                10 + if sample_depth == 8 { 6 } else { 10 }
+            );
+            assert_eq!(SuggestedPalette::try_from(&chunk).unwrap(), palette);
+        }
+
+        let chunk = Chunk::new(SuggestedPalette::chunk_type(), b"name\0\x08\0\0".to_vec());
+        assert!(SuggestedPalette::try_from(&chunk).is_err());
+        let chunk = Chunk::new(SuggestedPalette::chunk_type(), b"name\0\x04".to_vec());
+        assert!(SuggestedPalette::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_offset_and_scale() {
+        let offset = ImageOffset {
+            x: -10,
+            y: 20,
+            unit: OffsetUnit::Micrometre,
+        };
+        assert_eq!(ImageOffset::try_from(&offset.to_chunk()).unwrap(), offset);
+
+        let scale = PhysicalScale {
+            unit: ScaleUnit::Metre,
+            pixel_width: "0.0001".to_string(),
+            pixel_height: "1.5e-4".to_string(),
+        };
+        let chunk = scale.to_chunk().unwrap();
+        assert_eq!(chunk.data(), b"\x010.0001\x001.5e-4");
+        assert_eq!(PhysicalScale::try_from(&chunk).unwrap(), scale);
+
+        for data in [
+            &b"\x01-1\x001"[..],
+            b"\x011\x000",
+            b"\x01inf\x001",
+            b"\x011\x001e999",
+            b"\x03\x011\x001",
+            b"\x011",
+        ] {
+            let chunk = Chunk::new(PhysicalScale::chunk_type(), data.to_vec());
+            assert!(PhysicalScale::try_from(&chunk).is_err(), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn test_ancillary_info_and_set_dpi() {
+        let mut png = Png::from_chunks(vec![
+            ihdr(ColorType::Indexed, 8).to_chunk(),
+            testing_palette(2).to_chunk().unwrap(),
+            Background::PaletteIndex(1).to_chunk(),
+            Histogram {
+                frequencies: vec![5, 6],
+            }
+            .to_chunk(),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), Vec::new()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let info = png.ancillary_info();
+        assert_eq!(info.background, Some(Background::PaletteIndex(1)));
+        assert_eq!(info.histogram.unwrap().frequencies, [5, 6]);
+        assert_eq!(info.physical_dimensions, None);
+
+        png.set_dpi(72);
+        png.set_dpi(300);
+        assert_eq!(
+            png.physical_dimensions().unwrap(),
+            Some(PhysicalDimensions::from_dpi(300))
+        );
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            types,
+            ["IHDR", "PLTE", "bKGD", "hIST", "pHYs", "IDAT", "IEND"]
+        );
+        assert!(png.validate().is_empty());
+    }
+
+    #[test]
+    fn test_ancillary_info_skips_bad_chunks() {
+        let png = Png::from_chunks(vec![
+            ihdr(ColorType::Indexed, 8).to_chunk(),
+            testing_palette(2).to_chunk().unwrap(),
+            Chunk::new(PhysicalDimensions::chunk_type(), vec![0; 3]),
+            Background::PaletteIndex(1).to_chunk(),
+            Histogram {
+                frequencies: vec![5, 6, 7],
+            }
+            .to_chunk(),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), Vec::new()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let info = png.ancillary_info();
+        assert_eq!(info.background, Some(Background::PaletteIndex(1)));
+        assert_eq!(info.physical_dimensions, None);
+        assert_eq!(info.histogram, None);
+        let indices: Vec<Option<usize>> = info
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.chunk_index)
+            .collect();
+        assert_eq!(indices, [Some(2), Some(4)]);
+        assert!(info.to_string().contains("background: palette index 1"));
+    }
+}
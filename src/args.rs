@@ -1,138 +1,106 @@
-use anyhow::Error;
-
-use crate::chunk::{self, Chunk};
-use crate::chunk_type::{self, ChunkType};
-use crate::commands::{Commands, DataCommand, EncodeCommand, OperationResult};
-use crate::png::{self, Png};
-use crate::{check_string, operation, read_file, write_file};
-use std::io::{self, Write};
-use std::str::FromStr;
-// use crate::
-
-// struct
-#[derive(Debug)]
+use clap::Parser;
+
+use crate::commands::Commands;
+
+/// Hide messages in PNG files and inspect their chunks.
+#[derive(Debug, Parser)]
+#[command(name = "pngme", version)]
 pub struct Config {
+    #[command(subcommand)]
     pub command: Commands,
 }
 
 impl Config {
-    pub fn build(args: &[String]) -> Result<Config, anyhow::Error> {
-        if args.len() < 4 {
-            let message = format!(
-                "Not enough arguments provided: expected at least 4, got {}",
-                args.len()
-            );
-            return Err(Error::msg(message));
-        }
-
-        let data = read_file(&args[2].to_string())?;
-        let chunk_type = ChunkType::from_str(args[3].as_str())?;
-
-        let cmd: Commands = match args[1].as_str() {
-            "encode" => {
-                let message = args[4].clone();
-                let output: String = if args.len() > 4 {
-                    match check_string(&args.get(5).unwrap()) {
-                        Some(arg) => arg.to_string(),
-                        None => String::new(),
-                    }
-                } else {
-                    String::new()
-                };
-                Commands::Encode(EncodeCommand::new(data, chunk_type, message, output))
-            }
-            "decode" => Commands::Decode(DataCommand::new(data, chunk_type)),
-            "remove" => Commands::Remove(DataCommand::new(data, chunk_type)),
-            "print" => Commands::Print(DataCommand::new(data, chunk_type)),
-            _ => panic!("Invalid command. Use encode, decode, remove, or print."),
-        };
-        Ok(Config { command: cmd })
+    /// Parses the full argument list, including the program name in `args[0]`.
+    /// `--help` and `--version` are reported as errors of the matching kind.
+    pub fn build(args: &[String]) -> Result<Config, clap::Error> {
+        Config::try_parse_from(args)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use anyhow::Ok;
-
     use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::commands::OperationResult;
+    use crate::error::PngError;
+    use crate::png::Png;
+    use crate::text::TextEntry;
+    use crate::{operation, read_file, write_file};
+    use clap::error::ErrorKind;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    // Every test works on its own files so they can run in parallel.
+    fn temp_path(name: &str) -> String {
+        let dir: PathBuf = std::env::temp_dir().join("pngme_args_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().into_owned()
+    }
 
-    fn read_and_write_png() -> Png {
-        let mut png = encode_png_from_file().unwrap();
+    fn png_file(name: &str) -> String {
+        let ihdr = Chunk::new(
+            ChunkType::from_str("IHDR").unwrap(),
+            vec![0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0],
+        );
+        let iend = Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new());
+        let png = Png::from_chunks(vec![ihdr, iend]);
+        let path = temp_path(&format!("{}.png", name));
+        write_file(&path, png.as_bytes()).unwrap();
+        path
+    }
+
+    fn read_and_write_png(name: &str) -> String {
+        let mut png = encode_png_from_file(&png_file(name)).unwrap();
         let chunk_type = ChunkType::from_str("ruSt").unwrap();
         let chunk = Chunk::new(chunk_type, "Test Message".as_bytes().to_vec());
         png.append_chunk(chunk);
-        let temp_png_file = "temp_png";
-        let _ = write_file(&temp_png_file.to_string(), png.as_bytes()).unwrap();
-        png
+        let temp_png_file = temp_path(&format!("temp_{}.png", name));
+        write_file(&temp_png_file, png.as_bytes()).unwrap();
+        temp_png_file
     }
 
-    fn encode_png_from_file() -> Result<Png, String> {
-        let data = read_file("png_file.png").unwrap();
-
-        let mut png = match Png::try_from(data.as_slice()) {
-            std::result::Result::Ok(png) => png,
-            Err(e) => {
-                return Err(format!("Failed to decode PNG: {}", e)); // Assuming your function returns `Result<Png, String>`
-            }
-        };
-        std::result::Result::Ok(png)
+    fn encode_png_from_file(path: &str) -> Result<Png, PngError> {
+        let data = read_file(path)?;
+        Png::try_from(data.as_slice())
     }
 
-    fn build_config_encode() -> Config {
-        Config::build(&[
-            "0".to_string(),
-            "encode".to_string(),
-            "png_file.png".to_string(),
-            "ruSt".to_string(),
-            "This is a secret message!".to_string(),
-            "new_png".to_string(),
-        ])
-        .unwrap()
+    fn build(args: &[&str]) -> Result<Config, clap::Error> {
+        let args: Vec<String> = std::iter::once("pngme")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect();
+        Config::build(&args)
     }
 
-    fn verify_build_config_decode() -> Config {
-        Config::build(&[
-            "0".to_string(),
-            "decode".to_string(),
-            "new_png.png".to_string(),
-            "ruSt".to_string(),
+    fn build_config_encode(input: &str, output: &str) -> Config {
+        build(&[
+            "encode",
+            input,
+            "ruSt",
+            "This is a secret message!",
+            "--output",
+            output,
         ])
         .unwrap()
     }
 
-    fn unverify_build_config_decode() -> Config {
-        Config::build(&[
-            "0".to_string(),
-            "decode".to_string(),
-            "png_file.png".to_string(),
-            "ruSt".to_string(),
-        ])
-        .unwrap()
+    fn build_config_decode(input: &str) -> Config {
+        build(&["decode", input, "ruSt"]).unwrap()
     }
 
-    fn build_config_remove() -> Config {
-        Config::build(&[
-            "0".to_string(),
-            "remove".to_string(),
-            "temp_png.png".to_string(),
-            "ruSt".to_string(), // Assuming "ruSt" is the chunk type to be removed
-        ])
-        .unwrap()
+    fn build_config_remove(input: &str, output: &str) -> Config {
+        build(&["remove", input, "ruSt", "--output", output]).unwrap()
     }
 
-    fn build_config_print() -> Config {
-        Config::build(&[
-            "0".to_string(),
-            "print".to_string(),
-            "temp_png.png".to_string(),
-            "ruSt".to_string(), // Assuming "ruSt" is the chunk type to be removed
-        ])
-        .unwrap()
+    fn build_config_print(input: &str) -> Config {
+        build(&["print", input, "ruSt"]).unwrap()
     }
 
     #[test]
     fn encode_png() {
-        let config = build_config_encode();
+        let config = build_config_encode(&png_file("encode"), &temp_path("encode_out.png"));
         let operation_result = operation(&config).unwrap();
 
         match operation_result {
@@ -141,59 +109,474 @@ mod tests {
                     png.chunk_by_type("ruSt").unwrap().chunk_type().to_string(),
                     "ruSt"
                 );
+                // The secret chunk must not end up after IEND.
+                let bytes = png.as_bytes();
+                assert_eq!(&bytes[bytes.len() - 8..bytes.len() - 4], b"IEND");
             }
             _ => panic!("Expected EncodedPng variant"),
         }
     }
+
+    #[test]
+    fn encode_in_place() {
+        let path = png_file("in_place");
+        let config = build(&["encode", &path, "ruSt", "in place", "--in-place"]).unwrap();
+        operation(&config).unwrap();
+
+        let png = encode_png_from_file(&path).unwrap();
+        assert_eq!(
+            png.chunk_by_type("ruSt").unwrap().data_as_string().unwrap(),
+            "in place"
+        );
+    }
+
+    #[test]
+    fn encode_and_remove_touch_time() {
+        let path = png_file("touch");
+        let config = build(&["encode", &path, "ruSt", "message", "-i"]).unwrap();
+        operation(&config).unwrap();
+        assert!(encode_png_from_file(&path)
+            .unwrap()
+            .last_modified()
+            .unwrap()
+            .is_none());
+
+        let config = build(&["encode", &path, "ruSt", "again", "-i", "--touch"]).unwrap();
+        operation(&config).unwrap();
+        let png = encode_png_from_file(&path).unwrap();
+        assert!(png.last_modified().unwrap().is_some());
+
+        let config = build(&["remove", &path, "ruSt", "--all", "-i", "-t"]).unwrap();
+        operation(&config).unwrap();
+        let png = encode_png_from_file(&path).unwrap();
+        assert!(png.last_modified().unwrap().is_some());
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn encode_refuses_reserved_bit_unless_forced() {
+        let path = png_file("reserved");
+
+        let config = build(&["encode", &path, "rust", "message", "-i"]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::ReservedBitSet(_))
+        ));
+
+        let config = build(&["encode", &path, "rust", "message", "-i", "--force"]).unwrap();
+        assert!(operation(&config).is_ok());
+    }
+
     #[test]
     fn verify_secret_message_in_decoded_png() {
-        let config = verify_build_config_decode();
+        let output = temp_path("verify_out.png");
+        operation(&build_config_encode(&png_file("verify"), &output)).unwrap();
+
+        let config = build_config_decode(&output);
         let operation_result = operation(&config).unwrap();
 
         match operation_result {
             OperationResult::DecodedMessage(message) => {
                 assert_eq!(message, "This is a secret message!");
             }
-            _ => println!("Expected decoded variant"),
+            _ => panic!("Expected DecodedMessage variant"),
         }
     }
     #[test]
     fn unverify_secret_message_in_decoded_png() {
-        let config = unverify_build_config_decode();
-        // println!("==================");
+        let config = build_config_decode(&png_file("unverify"));
 
-        let operation_result = operation(&config).unwrap();
-        // println!("==================");
+        let operation_result = operation(&config);
 
-        match operation_result {
-            OperationResult::DecodedMessage(message) => {
-                assert_eq!(message, "Chunk not found");
-            }
-            _ => println!("Expected decoded variant"),
-        }
+        assert!(matches!(
+            operation_result,
+            Err(PngError::ChunkNotFound(chunk_type)) if chunk_type == "ruSt"
+        ));
     }
 
     #[test]
     fn remove_chunk_from_png() {
-        let _ = read_and_write_png();
+        let path = read_and_write_png("remove");
+        let output = temp_path("remove_out.png");
 
-        let config = build_config_remove();
+        let config = build_config_remove(&path, &output);
         let operation_result = operation(&config).unwrap();
 
         match operation_result {
-            OperationResult::RemovedChunk(removed_chunk) => {
+            OperationResult::RemovedChunks(removed_chunks) => {
                 // Check if the chunk was successfully removed
-                assert_eq!(removed_chunk.chunk_type().to_string(), "ruSt");
+                assert_eq!(removed_chunks.len(), 1);
+                assert_eq!(removed_chunks[0].chunk_type().to_string(), "ruSt");
+            }
+            _ => panic!("Expected RemovedChunks variant"),
+        }
+        assert!(encode_png_from_file(&output)
+            .unwrap()
+            .chunk_by_type("ruSt")
+            .is_none());
+        // The input is left untouched.
+        assert!(encode_png_from_file(&path)
+            .unwrap()
+            .chunk_by_type("ruSt")
+            .is_some());
+    }
+
+    #[test]
+    fn remove_all_chunks_in_place() {
+        let path = read_and_write_png("remove_all");
+        let mut png = encode_png_from_file(&path).unwrap();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"second".to_vec(),
+        ));
+        write_file(&path, png.as_bytes()).unwrap();
+
+        let config = build(&["remove", &path, "ruSt", "--all", "--in-place"]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::RemovedChunks(removed_chunks) => assert_eq!(removed_chunks.len(), 2),
+            _ => panic!("Expected RemovedChunks variant"),
+        }
+
+        let png = encode_png_from_file(&path).unwrap();
+        assert!(png.chunk_by_type("ruSt").is_none());
+        assert_eq!(png.chunks().len(), 2);
+    }
+
+    #[test]
+    fn remove_requires_a_destination() {
+        let err = build(&["remove", "file.png", "ruSt"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+
+        let err = build(&["remove", "file.png", "ruSt", "--stdout", "--in-place"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn encode_requires_a_destination() {
+        let err = build(&["encode", "file.png", "ruSt", "message"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+
+        let err = build(&["encode", "file.png", "ruSt", "message", "--stdout", "-i"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+
+        let config = build(&["encode", "file.png", "ruSt", "message", "--stdout"]).unwrap();
+        match &config.command {
+            Commands::Encode(encode_cmd) => assert!(encode_cmd.destination().stdout()),
+            other => panic!("unexpected command {:?}", other),
+        }
+    }
+
+    #[test]
+    fn info_from_png() {
+        let config = build(&["info", &png_file("info")]).unwrap();
+
+        match operation(&config).unwrap() {
+            OperationResult::HeaderInfo(ihdr) => {
+                assert_eq!(ihdr.width, 1);
+                assert_eq!(ihdr.height, 1);
+            }
+            _ => panic!("Expected HeaderInfo variant"),
+        }
+    }
+
+    #[test]
+    fn info_all_from_png() {
+        let path = png_file("info_all");
+        let mut png = encode_png_from_file(&path).unwrap();
+        png.set_dpi(300);
+        // A truncated tIME is reported without hiding the rest of the view.
+        png.append_chunk(Chunk::new(ChunkType::from_str("tIME").unwrap(), vec![0; 3]));
+        write_file(&path, png.as_bytes()).unwrap();
+
+        let config = build(&["info", &path, "--all"]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::FullInfo {
+                header,
+                palette,
+                ancillary,
+                last_modified,
+                diagnostics,
+                ..
+            } => {
+                assert_eq!(header.width, 1);
+                assert!(palette.is_none());
+                assert!(last_modified.is_none());
+                let dpi = ancillary.physical_dimensions.unwrap().dpi().unwrap();
+                assert_eq!(dpi.0.round(), 300.0);
+                assert_eq!(diagnostics.len(), 1);
+                assert!(diagnostics[0].message.contains("tIME"));
+            }
+            _ => panic!("Expected FullInfo variant"),
+        }
+    }
+
+    #[test]
+    fn validate_png() {
+        let config = build(&["validate", &png_file("validate")]).unwrap();
+
+        match operation(&config).unwrap() {
+            OperationResult::Validated(diagnostics) => {
+                // The fixture has no IDAT chunk.
+                assert_eq!(diagnostics.len(), 1);
+                assert!(diagnostics[0].message.contains("IDAT"));
+            }
+            _ => panic!("Expected Validated variant"),
+        }
+    }
+
+    #[test]
+    fn compare_decoded_images() {
+        use crate::ihdr::{ColorType, Ihdr, InterlaceMethod};
+        use crate::zlib;
+
+        let image = |name: &str, pixels: &[u8]| {
+            let ihdr = Ihdr {
+                width: 2,
+                height: 1,
+                bit_depth: 8,
+                color_type: ColorType::Rgb,
+                compression_method: 0,
+                filter_method: 0,
+                interlace_method: InterlaceMethod::None,
+            };
+            let scanline: Vec<u8> = std::iter::once(0).chain(pixels.iter().copied()).collect();
+            let png = Png::from_chunks(vec![
+                ihdr.to_chunk(),
+                Chunk::new(
+                    ChunkType::from_str("IDAT").unwrap(),
+                    zlib::compress(&scanline),
+                ),
+                Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+            ]);
+            let path = temp_path(&format!("{}.png", name));
+            write_file(&path, png.as_bytes()).unwrap();
+            path
+        };
+        let original = image("compare", &[1, 2, 3, 4, 5, 6]);
+        let edited = temp_path("compare_edited.png");
+        let config = build(&["text", "set", &original, "Title", "Dice", "-o", &edited]).unwrap();
+        operation(&config).unwrap();
+
+        let config = build(&["compare", &original, &edited]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::Compared(difference) => assert_eq!(difference, None),
+            _ => panic!("Expected Compared variant"),
+        }
+
+        let changed = image("compare_changed", &[1, 2, 3, 4, 5, 7]);
+        let config = build(&["compare", &original, &changed]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::Compared(difference) => {
+                assert_eq!(difference.unwrap().to_string(), "pixel (1, 0) differs");
+            }
+            _ => panic!("Expected Compared variant"),
+        }
+    }
+
+    #[test]
+    fn list_chunks_in_png() {
+        let config = build(&["list", &read_and_write_png("list"), "--json"]).unwrap();
+
+        match operation(&config).unwrap() {
+            OperationResult::ChunkList { chunks, json } => {
+                assert!(json);
+                let types: Vec<String> = chunks.iter().map(|c| c.chunk_type.to_string()).collect();
+                assert_eq!(types, ["IHDR", "ruSt", "IEND"]);
+                assert_eq!(chunks[1].offset, 8 + 25);
+            }
+            _ => panic!("Expected ChunkList variant"),
+        }
+    }
+
+    #[test]
+    fn palette_from_png() {
+        let path = png_file("palette");
+        let config = build(&["palette", &path]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::ChunkNotFound(chunk_type)) if chunk_type == "PLTE"
+        ));
+
+        let mut png = encode_png_from_file(&path).unwrap();
+        // Switch the fixture to 8-bit indexed colour.
+        let mut ihdr = png.header_info().unwrap();
+        ihdr.color_type = crate::ihdr::ColorType::Indexed;
+        png.remove_chunk("IHDR").unwrap();
+        png.insert_chunk_at(0, ihdr.to_chunk()).unwrap();
+        png.insert_after(
+            "IHDR",
+            Chunk::new(
+                ChunkType::from_str("PLTE").unwrap(),
+                vec![255, 0, 0, 0, 0, 255],
+            ),
+        )
+        .unwrap();
+        png.insert_after(
+            "PLTE",
+            Chunk::new(ChunkType::from_str("tRNS").unwrap(), vec![0]),
+        )
+        .unwrap();
+        write_file(&path, png.as_bytes()).unwrap();
+
+        let config = build(&["palette", &path, "--json"]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::PaletteInfo {
+                palette,
+                transparency,
+                json,
+            } => {
+                assert!(json);
+                assert_eq!(palette.entries.len(), 2);
+                assert_eq!(transparency.unwrap().alpha(0), 0);
+            }
+            _ => panic!("Expected PaletteInfo variant"),
+        }
+    }
+
+    #[test]
+    fn color_show_strip_extract() {
+        use crate::color::{Gamma, IccProfile};
+
+        let path = png_file("color");
+        let mut png = encode_png_from_file(&path).unwrap();
+        png.insert_after("IHDR", Gamma { value: 45455 }.to_chunk())
+            .unwrap();
+        let icc = IccProfile {
+            name: "Test profile".to_string(),
+            profile: b"not really an ICC profile".to_vec(),
+        };
+        png.insert_after("IHDR", icc.to_chunk().unwrap()).unwrap();
+        write_file(&path, png.as_bytes()).unwrap();
+
+        let config = build(&["color", "show", &path]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::ColorInfo(info) => {
+                assert_eq!(info.gamma, Some(Gamma { value: 45455 }));
+                assert_eq!(info.icc_profile.as_ref(), Some(&icc));
+            }
+            _ => panic!("Expected ColorInfo variant"),
+        }
+
+        let config = build(&["color", "extract-icc", &path]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::ExtractedProfile { path, .. } => {
+                assert_eq!(path.extension().unwrap(), "icc");
+                assert_eq!(read_file(&path).unwrap(), icc.profile);
+            }
+            _ => panic!("Expected ExtractedProfile variant"),
+        }
+
+        let config = build(&["color", "strip", &path, "-i"]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::RemovedChunks(removed) => assert_eq!(removed.len(), 2),
+            _ => panic!("Expected RemovedChunks variant"),
+        }
+        let config = build(&["color", "extract-icc", &path]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::ChunkNotFound(chunk_type)) if chunk_type == "iCCP"
+        ));
+    }
+
+    #[test]
+    fn exif_show_remove_tag_strip() {
+        use crate::exif::{ByteOrder, Exif, ExifEntry, ExifValue, Ifd};
+
+        let path = png_file("exif");
+        let mut png = encode_png_from_file(&path).unwrap();
+        let entry = |ifd, tag, text: &str| ExifEntry {
+            ifd,
+            tag,
+            value: ExifValue::Ascii(text.as_bytes().to_vec()),
+        };
+        png.set_exif(&Exif {
+            byte_order: ByteOrder::LittleEndian,
+            entries: vec![
+                entry(Ifd::Primary, 0x010f, "Maker"),
+                entry(Ifd::Exif, 0xa431, "SN-1"),
+                entry(Ifd::Gps, 0x0001, "N"),
+            ],
+            thumbnail: None,
+        });
+        write_file(&path, png.as_bytes()).unwrap();
+
+        let output = temp_path("exif_removed.png");
+        let config = build(&[
+            "exif",
+            "remove-tag",
+            &path,
+            "GPS",
+            "BodySerialNumber",
+            "-o",
+            &output,
+        ])
+        .unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::RemovedExifTags(removed) => assert_eq!(removed.len(), 2),
+            _ => panic!("Expected RemovedExifTags variant"),
+        }
+
+        let config = build(&["exif", "show", &output]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::ExifTags(exif) => {
+                assert_eq!(exif.entries, vec![entry(Ifd::Primary, 0x010f, "Maker")]);
+            }
+            _ => panic!("Expected ExifTags variant"),
+        }
+        assert!(build(&["exif", "remove-tag", &path, "NoSuchTag", "-i"]).is_err());
+
+        // Nothing matches, so the eXIf chunk is left exactly as it was.
+        let before = read_file(&output).unwrap();
+        let config = build(&["exif", "remove-tag", &output, "GPS", "-i"]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::ChunkNotFound(_))
+        ));
+        assert_eq!(read_file(&output).unwrap(), before);
+
+        let config = build(&["exif", "strip", &output, "-i"]).unwrap();
+        operation(&config).unwrap();
+        let config = build(&["exif", "show", &output]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::ChunkNotFound(chunk_type)) if chunk_type == "eXIf"
+        ));
+    }
+
+    #[test]
+    fn xmp_set_get_show() {
+        let path = png_file("xmp");
+        let config = build(&["xmp", "get", &path, "rights"]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::ChunkNotFound(_))
+        ));
+
+        let config = build(&["xmp", "set", &path, "rights", "© PNGMe", "-i"]).unwrap();
+        operation(&config).unwrap();
+        let config = build(&["xmp", "set", &path, "dc:creator", "Ferris", "-i"]).unwrap();
+        operation(&config).unwrap();
+
+        let config = build(&["xmp", "get", &path, "rights"]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::DecodedMessage(value) => assert_eq!(value, "© PNGMe"),
+            _ => panic!("Expected DecodedMessage variant"),
+        }
+        let config = build(&["xmp", "show", &path]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::DecodedMessage(xml) => {
+                assert!(xml.contains("<rdf:li>Ferris</rdf:li>"));
             }
-            _ => panic!("Expected RemovedChunk variant"),
+            _ => panic!("Expected DecodedMessage variant"),
         }
+        assert!(build(&["xmp", "get", &path, "title"]).is_err());
     }
 
     #[test]
     fn print_chunk_from_png() {
-        let _ = read_and_write_png();
+        let path = read_and_write_png("print");
 
-        let config = build_config_print();
+        let config = build_config_print(&path);
         let operation_result = operation(&config).unwrap();
         match operation_result {
             OperationResult::PrintedInfo(result) => {
@@ -202,4 +585,137 @@ mod tests {
             _ => panic!("Expected PrintedInfo variant"),
         }
     }
+
+    #[test]
+    fn text_set_get_list_remove() {
+        let path = png_file("text");
+        let config = build(&["text", "set", &path, "Title", "Dice", "--in-place"]).unwrap();
+        operation(&config).unwrap();
+        let config = build(&["text", "set", &path, "Author", "Zoë", "-i", "--compress"]).unwrap();
+        operation(&config).unwrap();
+
+        let config = build(&["decode", &path, "zTXt"]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::DecodedMessage(text) => assert_eq!(text, "Zoë"),
+            _ => panic!("Expected DecodedMessage variant"),
+        }
+
+        let config = build(&["text", "get", &path, "Title"]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::DecodedMessage(text) => assert_eq!(text, "Dice"),
+            _ => panic!("Expected DecodedMessage variant"),
+        }
+
+        let config = build(&["text", "list", &path]).unwrap();
+        match operation(&config).unwrap() {
+            OperationResult::TextEntries { entries, .. } => {
+                let keywords: Vec<&str> = entries.iter().map(|e| e.keyword()).collect();
+                assert_eq!(keywords, ["Title", "Author"]);
+                assert!(matches!(entries[1], TextEntry::Compressed(_)));
+            }
+            _ => panic!("Expected TextEntries variant"),
+        }
+
+        let output = temp_path("text_removed.png");
+        let config = build(&["text", "remove", &path, "Title", "--output", &output]).unwrap();
+        operation(&config).unwrap();
+        let png = encode_png_from_file(&output).unwrap();
+        assert!(png.text("Title").unwrap().is_none());
+        assert_eq!(png.text("Author").unwrap().unwrap().text(), "Zoë");
+
+        let config = build(&["text", "get", &output, "Title"]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn text_set_international() {
+        let path = png_file("text_international");
+        let config = build(&[
+            "text",
+            "set",
+            &path,
+            "Title",
+            "Würfel 🎲",
+            "--international",
+            "--lang",
+            "de",
+            "--translated",
+            "Titel",
+            "-i",
+        ])
+        .unwrap();
+        operation(&config).unwrap();
+
+        let png = encode_png_from_file(&path).unwrap();
+        match png.text("Title").unwrap() {
+            Some(TextEntry::International(entry)) => {
+                assert_eq!(entry.text, "Würfel 🎲");
+                assert_eq!(entry.language_tag, "de");
+                assert_eq!(entry.translated_keyword, "Titel");
+            }
+            other => panic!("Expected an iTXt entry, got {:?}", other),
+        }
+
+        let err = build(&["text", "set", &path, "Title", "x", "--lang", "de", "-i"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn text_set_rejects_invalid_keyword() {
+        let path = png_file("text_keyword");
+        let config = build(&["text", "set", &path, " Title", "Dice", "-i"]).unwrap();
+        assert!(matches!(
+            operation(&config),
+            Err(PngError::InvalidChunkData { .. })
+        ));
+
+        let err = build(&["text", "set", &path, "Title", "Dice"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn unknown_command_is_an_error() {
+        let err = build(&["explode", "file.png"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidSubcommand);
+    }
+
+    #[test]
+    fn missing_arguments_are_an_error() {
+        let err = build(&["decode", "file.png"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn invalid_chunk_type_is_an_error() {
+        let err = build(&["decode", "file.png", "ru1t"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn output_conflicts_with_in_place() {
+        let err = build(&[
+            "encode",
+            "file.png",
+            "ruSt",
+            "message",
+            "--in-place",
+            "--output",
+            "out.png",
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn help_is_available_for_every_command() {
+        let err = build(&["--help"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DisplayHelp);
+
+        let err = build(&["encode", "--help"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DisplayHelp);
+        assert!(err.to_string().contains("--in-place"));
+    }
 }
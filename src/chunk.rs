@@ -1,20 +1,18 @@
-use std::{error, path::Display};
-// use std::result::Result::Ok;
-use anyhow::{Error, Ok};
-use crc::{self, Crc, CRC_32_ISO_HDLC};
+use crc::{Crc, CRC_32_ISO_HDLC};
 use std::fmt;
 
-// mod chunk_type;
-// use crate::pngme::chunk_type;
-use crate::chunk_type::{self, ChunkType};
+use crate::chunk_ref::ChunkRef;
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
 
-fn convert_into_checksum(crc: u32, chunk_type: ChunkType, data: Vec<u8>) -> u32 {
-    let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-    let mut digest = crc.digest();
+/// The CRC-32 variant PNG uses, computed over the chunk type and data.
+pub(crate) static PNG_CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+pub(crate) fn convert_into_checksum(chunk_type: ChunkType, data: &[u8]) -> u32 {
+    let mut digest = PNG_CRC.digest();
     digest.update(chunk_type.bytes().as_slice());
-    digest.update(data.as_slice());
-    let calculated_crc = digest.finalize();
-    calculated_crc
+    digest.update(data);
+    digest.finalize()
 }
 #[derive(Debug)]
 pub struct Chunk {
@@ -26,25 +24,31 @@ pub struct Chunk {
 
 impl Chunk {
     pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Chunk {
-        let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-        let mut digest = crc.digest();
-        digest.update(chunk_type.bytes().as_slice());
-        digest.update(data.as_slice());
-        let calculated_crc = digest.finalize();
+        let calculated_crc = convert_into_checksum(chunk_type, &data);
 
         Chunk {
             data_length: data.len() as u32,
-            chunk_type: chunk_type,
+            chunk_type,
             chunk_data: data,
             crc: calculated_crc,
         }
     }
 
+    /// Builds a chunk keeping the CRC exactly as it was stored, even if it is wrong.
+    pub(crate) fn with_stored_crc(chunk_type: ChunkType, data: Vec<u8>, crc: u32) -> Chunk {
+        Chunk {
+            data_length: data.len() as u32,
+            chunk_type,
+            chunk_data: data,
+            crc,
+        }
+    }
+
     pub fn length(&self) -> u32 {
         self.data_length
     }
     pub fn chunk_type(&self) -> ChunkType {
-        self.chunk_type.clone()
+        self.chunk_type
     }
     pub fn data(&self) -> &[u8] {
         &self.chunk_data
@@ -52,11 +56,26 @@ impl Chunk {
     pub fn crc(&self) -> u32 {
         self.crc
     }
-    pub fn data_as_string(&self) -> Result<String, Error> {
-        match String::from_utf8(self.chunk_data.clone()) {
-            std::result::Result::Ok(data) => Ok(data),
-            Err(e) => Err(e.into()),
+    /// The CRC the chunk type and data actually hash to.
+    pub fn computed_crc(&self) -> u32 {
+        convert_into_checksum(self.chunk_type, &self.chunk_data)
+    }
+    pub fn crc_is_valid(&self) -> bool {
+        self.crc == self.computed_crc()
+    }
+    pub(crate) fn verify_crc(&self) -> Result<()> {
+        let calculated = self.computed_crc();
+        if calculated != self.crc {
+            return Err(PngError::CrcMismatch {
+                chunk_type: self.chunk_type,
+                expected: calculated,
+                actual: self.crc,
+            });
         }
+        Ok(())
+    }
+    pub fn data_as_string(&self) -> Result<String> {
+        Ok(String::from_utf8(self.chunk_data.clone())?)
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
@@ -91,44 +110,20 @@ impl fmt::Display for Chunk {
             "chunk length is {} and chunk_type {} and chunk data {} and chuck crc is {} ",
             self.length(),
             self.chunk_type(),
-            String::from_utf8(self.data().to_vec()).unwrap(),
+            String::from_utf8_lossy(self.data()),
             self.crc()
         )
     }
 }
 
 impl TryFrom<&[u8]> for Chunk {
-    type Error = anyhow::Error;
-
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        // println!("i am here i have issue: ======> {:?}", &value);
-        let length = u32::from_be_bytes(value[0..4].try_into()?);
-        // println!("i am here i have issue: ===>{:?}", &value);
-
-        let chunk_type = &value[4..8];
-        // println!("i am here i have issue: ===>{:?}", &value);
-
-        let message = &value[8..(8 + length as usize)];
-        // println!("i am here i have issue: ===>{:?}", &value);
+    type Error = PngError;
 
-        let crc = u32::from_be_bytes(value[(8 + length as usize)..].try_into()?);
+    fn try_from(value: &[u8]) -> Result<Self> {
+        let chunk = ChunkRef::try_from(value)?;
+        chunk.verify_crc()?;
 
-        // println!("i am here i have issue: ===>{:?}", &value);
-
-        let chunk = Chunk {
-            data_length: length,
-            chunk_type: ChunkType::new(chunk_type.try_into()?),
-            chunk_data: message.to_vec(),
-            crc: crc,
-        };
-
-        let crc = convert_into_checksum(chunk.crc(), chunk.chunk_type(), chunk.data().to_vec());
-
-        if crc != chunk.crc() {
-            return Err(anyhow::anyhow!("CRC check failed"));
-        }
-
-        Ok(chunk)
+        Ok(chunk.to_owned())
     }
 }
 
@@ -242,6 +237,47 @@ mod tests {
 
         assert!(chunk.is_err());
     }
+
+    #[test]
+    fn test_crc_mismatch_reports_both_crcs() {
+        let mut chunk_data = testing_chunk().as_bytes();
+        let last = chunk_data.len() - 1;
+        chunk_data[last] ^= 1;
+
+        match Chunk::try_from(chunk_data.as_ref()) {
+            Err(PngError::CrcMismatch {
+                expected, actual, ..
+            }) => {
+                assert_eq!(expected, 2882656334);
+                assert_eq!(actual, 2882656334 ^ 1);
+            }
+            other => panic!("expected CrcMismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_truncated_chunk_from_bytes() {
+        let chunk_data = testing_chunk().as_bytes();
+        let chunk = Chunk::try_from(&chunk_data[..52]);
+        assert!(matches!(
+            chunk,
+            Err(PngError::TruncatedChunk {
+                needed: 54,
+                available: 52,
+                ..
+            })
+        ));
+
+        let chunk = Chunk::try_from(&chunk_data[..20]);
+        assert!(matches!(
+            chunk,
+            Err(PngError::LengthOverflow {
+                length: 42,
+                remaining: Some(12),
+                ..
+            })
+        ));
+    }
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
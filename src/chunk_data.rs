@@ -0,0 +1,36 @@
+// Helpers shared by the typed chunk decoders.
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+
+pub(crate) fn invalid(chunk_type: ChunkType, reason: String) -> PngError {
+    PngError::InvalidChunkData { chunk_type, reason }
+}
+
+/// Fails with `ChunkNotFound` unless `chunk` is of type `chunk_type`.
+pub(crate) fn check_type(chunk: &Chunk, chunk_type: ChunkType) -> Result<()> {
+    if chunk.chunk_type() != chunk_type {
+        return Err(PngError::ChunkNotFound(chunk_type.to_string()));
+    }
+    Ok(())
+}
+
+/// Fails with `InvalidChunkData` unless `chunk` holds exactly `length` bytes.
+pub(crate) fn expect_length(chunk: &Chunk, length: usize) -> Result<()> {
+    if chunk.data().len() != length {
+        return Err(invalid(
+            chunk.chunk_type(),
+            format!("expected {} bytes, got {}", length, chunk.data().len()),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u16(data: &[u8], i: usize) -> u16 {
+    u16::from_be_bytes([data[i], data[i + 1]])
+}
+
+pub(crate) fn read_u32(data: &[u8], i: usize) -> u32 {
+    u32::from_be_bytes(data[i..i + 4].try_into().unwrap())
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::str;
+
+use crate::chunk::{convert_into_checksum, Chunk};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+
+/// A chunk borrowed from an input buffer. Nothing is copied and the CRC is
+/// only checked when asked for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChunkRef<'a> {
+    chunk_type: ChunkType,
+    data: &'a [u8],
+    crc: u32,
+}
+
+impl<'a> ChunkRef<'a> {
+    /// Reads the chunk starting at `offset`, returning it and the offset just
+    /// past it. Never reads beyond the end of `bytes`.
+    pub(crate) fn read_at(
+        bytes: &'a [u8],
+        offset: usize,
+        chunk_index: usize,
+    ) -> Result<(ChunkRef<'a>, usize)> {
+        let available = bytes.len().saturating_sub(offset);
+        if available < 8 {
+            return Err(PngError::TruncatedChunk {
+                chunk_index,
+                offset,
+                needed: 12,
+                available,
+            });
+        }
+
+        let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let remaining = available - 8;
+        if length > i32::MAX as u32 || length as usize > remaining {
+            return Err(PngError::LengthOverflow {
+                chunk_index,
+                offset,
+                length,
+                remaining: Some(remaining),
+            });
+        }
+        let needed = 12 + length as usize;
+        if available < needed {
+            return Err(PngError::TruncatedChunk {
+                chunk_index,
+                offset,
+                needed,
+                available,
+            });
+        }
+
+        let chunk_type: [u8; 4] = bytes[offset + 4..offset + 8].try_into().unwrap();
+        let chunk_type = ChunkType::try_from(chunk_type)?;
+
+        let data_end = offset + 8 + length as usize;
+        let data = &bytes[offset + 8..data_end];
+        let crc = u32::from_be_bytes(bytes[data_end..data_end + 4].try_into().unwrap());
+
+        Ok((
+            ChunkRef {
+                chunk_type,
+                data,
+                crc,
+            },
+            data_end + 4,
+        ))
+    }
+
+    pub fn length(&self) -> u32 {
+        self.data.len() as u32
+    }
+    pub fn chunk_type(&self) -> ChunkType {
+        self.chunk_type
+    }
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+    pub fn crc(&self) -> u32 {
+        self.crc
+    }
+    pub fn computed_crc(&self) -> u32 {
+        convert_into_checksum(self.chunk_type, self.data)
+    }
+    pub fn crc_is_valid(&self) -> bool {
+        self.crc == self.computed_crc()
+    }
+    pub fn verify_crc(&self) -> Result<()> {
+        let calculated = self.computed_crc();
+        if calculated != self.crc {
+            return Err(PngError::CrcMismatch {
+                chunk_type: self.chunk_type,
+                expected: calculated,
+                actual: self.crc,
+            });
+        }
+        Ok(())
+    }
+    /// The data as UTF-8 text, borrowed from the input.
+    pub fn data_as_str(&self) -> Result<&'a str> {
+        match str::from_utf8(self.data) {
+            Ok(text) => Ok(text),
+            // Only the error path copies, to build the owned error value.
+            Err(_) => Err(String::from_utf8(self.data.to_vec()).unwrap_err().into()),
+        }
+    }
+
+    /// Copies the chunk into an owned `Chunk`, keeping the stored CRC.
+    pub fn to_owned(&self) -> Chunk {
+        Chunk::with_stored_crc(self.chunk_type, self.data.to_vec(), self.crc)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ChunkRef<'a> {
+    type Error = PngError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        let (chunk, end) = ChunkRef::read_at(value, 0, 0)?;
+        if value.len() > end {
+            return Err(PngError::TrailingBytes(value.len() - end));
+        }
+        Ok(chunk)
+    }
+}
+
+impl fmt::Display for ChunkRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "chunk length is {} and chunk_type {} and chunk crc is {}",
+            self.length(),
+            self.chunk_type,
+            self.crc
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn testing_bytes() -> Vec<u8> {
+        Chunk::new(
+            ChunkType::from_str("RuSt").unwrap(),
+            "This is where your secret message will be!"
+                .as_bytes()
+                .to_vec(),
+        )
+        .as_bytes()
+    }
+
+    #[test]
+    fn test_chunk_ref_borrows_input() {
+        let bytes = testing_bytes();
+        let chunk = ChunkRef::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(chunk.length(), 42);
+        assert_eq!(chunk.chunk_type().to_string(), "RuSt");
+        assert_eq!(chunk.crc(), 2882656334);
+        assert!(std::ptr::eq(chunk.data().as_ptr(), bytes[8..].as_ptr()));
+        assert_eq!(
+            chunk.data_as_str().unwrap(),
+            "This is where your secret message will be!"
+        );
+    }
+
+    #[test]
+    fn test_chunk_ref_checks_crc_lazily() {
+        let mut bytes = testing_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+
+        let chunk = ChunkRef::try_from(bytes.as_slice()).unwrap();
+        assert!(!chunk.crc_is_valid());
+        assert!(matches!(
+            chunk.verify_crc(),
+            Err(PngError::CrcMismatch { .. })
+        ));
+        assert!(Chunk::try_from(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_chunk_ref_to_owned() {
+        let bytes = testing_bytes();
+        let chunk = ChunkRef::try_from(bytes.as_slice()).unwrap().to_owned();
+        assert_eq!(chunk.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_chunk_ref_rejects_trailing_bytes() {
+        let mut bytes = testing_bytes();
+        bytes.push(0);
+        assert!(matches!(
+            ChunkRef::try_from(bytes.as_slice()),
+            Err(PngError::TrailingBytes(1))
+        ));
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 use std::str::FromStr;
 
-use anyhow::Error;
+use crate::error::PngError;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct ChunkType {
@@ -11,8 +11,7 @@ pub struct ChunkType {
 impl fmt::Display for ChunkType {
     // need to add error handling in the future
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", String::from_utf8(self.bytes.to_vec()).unwrap());
-        Ok(())
+        write!(f, "{}", String::from_utf8_lossy(&self.bytes))
     }
 }
 
@@ -21,69 +20,57 @@ impl ChunkType {
         self.bytes
     }
 
-    pub fn new(val: [u8; 4]) -> ChunkType {
-        ChunkType { bytes: val }
-    }
-
+    /// A chunk type is valid when all four bytes are ASCII letters and the
+    /// reserved bit is clear.
     pub fn is_valid(&self) -> bool {
-        !self.is_public() && self.is_reserved_bit_valid()
+        self.bytes.iter().all(|b| b.is_ascii_alphabetic()) && self.is_reserved_bit_valid()
     }
 
+    /// Bit 5 of the first byte clear (uppercase): decoders must understand the chunk.
     pub fn is_critical(&self) -> bool {
-        if self.bytes[0].is_ascii_uppercase() {
-            return true;
-        }
-        false
+        self.bytes[0] & PROPERTY_BIT == 0
     }
-    fn is_public(&self) -> bool {
-        if self.bytes[1].is_ascii_uppercase() {
-            return true;
-        }
-        return false;
+
+    /// Bit 5 of the second byte clear (uppercase): the type is registered by the PNG spec.
+    pub fn is_public(&self) -> bool {
+        self.bytes[1] & PROPERTY_BIT == 0
     }
 
-    fn is_reserved_bit_valid(&self) -> bool {
-        if self.bytes[2].is_ascii_uppercase() {
-            return true;
-        }
-        return false;
+    /// Bit 5 of the third byte must be clear (uppercase) in this version of PNG.
+    pub fn is_reserved_bit_valid(&self) -> bool {
+        self.bytes[2] & PROPERTY_BIT == 0
     }
 
-    fn is_safe_to_copy(&self) -> bool {
-        if self.bytes[3].is_ascii_lowercase() {
-            return true;
-        }
-        return false;
+    /// Bit 5 of the fourth byte set (lowercase): editors may copy the chunk
+    /// even if they changed critical chunks.
+    pub fn is_safe_to_copy(&self) -> bool {
+        self.bytes[3] & PROPERTY_BIT != 0
     }
 }
 
-impl TryFrom<[i32; 4]> for ChunkType {
-    type Error = anyhow::Error;
+/// The case bit that carries each chunk type property.
+const PROPERTY_BIT: u8 = 1 << 5;
+
+impl TryFrom<[u8; 4]> for ChunkType {
+    type Error = PngError;
 
-    fn try_from(value: [i32; 4]) -> Result<Self, Self::Error> {
-        let byte_vec: [u8; 4] = value.map(|i| i as u8);
-        Ok(ChunkType { bytes: byte_vec })
+    fn try_from(value: [u8; 4]) -> Result<Self, Self::Error> {
+        if !value.iter().all(|b| b.is_ascii_alphabetic()) {
+            return Err(PngError::InvalidChunkType(value));
+        }
+        Ok(ChunkType { bytes: value })
     }
 }
 
 impl FromStr for ChunkType {
-    type Err = anyhow::Error;
+    type Err = PngError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 4 {
-            let error_msg = "Length is not equal to 4 ".to_string();
-            return Err(Error::msg(error_msg));
-        }
-
-        if !s.chars().all(|x| x.is_ascii_alphabetic()) {
-            let error_msg = "contains number".to_string();
-            return Err(Error::msg(error_msg));
-        }
-
-        let temp = s.as_bytes().to_vec();
-        Ok(ChunkType {
-            bytes: temp.try_into().unwrap(),
-        })
+        let bytes: [u8; 4] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| PngError::InvalidChunkTypeLength(s.len()))?;
+        ChunkType::try_from(bytes)
     }
 }
 
@@ -129,13 +116,48 @@ mod tests {
     #[test]
     pub fn test_invalid_chunk_is_valid() {
         let chunk = ChunkType::from_str("Rust").unwrap();
-        print!("{}", chunk);
         assert!(!chunk.is_valid());
 
         let chunk = ChunkType::from_str("Ru1t");
         assert!(chunk.is_err());
     }
 
+    #[test]
+    pub fn test_chunk_type_from_bytes_rejects_non_letters() {
+        assert!(matches!(
+            ChunkType::try_from([82, 117, 0, 116]),
+            Err(PngError::InvalidChunkType(_))
+        ));
+        assert!(ChunkType::try_from([82, 117, 91, 116]).is_err());
+    }
+
+    #[test]
+    pub fn test_chunk_type_properties_follow_bit_five() {
+        let chunk = ChunkType::from_str("bLOb").unwrap();
+        assert!(!chunk.is_critical());
+        assert!(chunk.is_public());
+        assert!(chunk.is_reserved_bit_valid());
+        assert!(chunk.is_safe_to_copy());
+        assert!(chunk.is_valid());
+
+        let chunk = ChunkType::from_str("IDAT").unwrap();
+        assert!(chunk.is_critical());
+        assert!(!chunk.is_safe_to_copy());
+        assert!(chunk.is_valid());
+    }
+
+    #[test]
+    pub fn test_chunk_type_from_str_errors() {
+        assert!(matches!(
+            ChunkType::from_str("Ru1t"),
+            Err(PngError::InvalidChunkType([82, 117, 49, 116]))
+        ));
+        assert!(matches!(
+            ChunkType::from_str("RuStt"),
+            Err(PngError::InvalidChunkTypeLength(5))
+        ));
+    }
+
     #[test]
     pub fn test_chunk_type_is_public() {
         let chunk = ChunkType::from_str("RUSt").unwrap();
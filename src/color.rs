@@ -0,0 +1,398 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_data::{check_type, expect_length, invalid, read_u32};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::png::Png;
+use crate::text::{check_keyword, decode_latin1, encode_latin1};
+use crate::zlib;
+
+/// gAMA and cHRM store values multiplied by this factor.
+pub const SCALE: f64 = 100_000.0;
+
+/// The decoded contents of the `gAMA` chunk: the image gamma times 100000.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Gamma {
+    pub value: u32,
+}
+
+impl Gamma {
+    pub const LENGTH: usize = 4;
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("gAMA").unwrap()
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.value as f64 / SCALE
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(Gamma::chunk_type(), self.value.to_be_bytes().to_vec())
+    }
+}
+
+impl TryFrom<&Chunk> for Gamma {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Gamma::chunk_type())?;
+        expect_length(chunk, Gamma::LENGTH)?;
+        let value = read_u32(chunk.data(), 0);
+        if value == 0 {
+            return Err(invalid(
+                Gamma::chunk_type(),
+                "gamma must not be zero".to_string(),
+            ));
+        }
+        Ok(Gamma { value })
+    }
+}
+
+impl fmt::Display for Gamma {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.5}", self.as_f64())
+    }
+}
+
+/// The decoded contents of the `cHRM` chunk: CIE x, y coordinates of the
+/// white point and primaries, each times 100000.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Chromaticities {
+    pub white: (u32, u32),
+    pub red: (u32, u32),
+    pub green: (u32, u32),
+    pub blue: (u32, u32),
+}
+
+impl Chromaticities {
+    pub const LENGTH: usize = 32;
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("cHRM").unwrap()
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = [self.white, self.red, self.green, self.blue]
+            .iter()
+            .flat_map(|&(x, y)| [x, y])
+            .flat_map(u32::to_be_bytes)
+            .collect();
+        Chunk::new(Chromaticities::chunk_type(), data)
+    }
+}
+
+impl TryFrom<&Chunk> for Chromaticities {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Chromaticities::chunk_type())?;
+        expect_length(chunk, Chromaticities::LENGTH)?;
+        let data = chunk.data();
+        let point = |i: usize| (read_u32(data, i), read_u32(data, i + 4));
+        Ok(Chromaticities {
+            white: point(0),
+            red: point(8),
+            green: point(16),
+            blue: point(24),
+        })
+    }
+}
+
+impl fmt::Display for Chromaticities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let points = [
+            ("white", self.white),
+            ("red", self.red),
+            ("green", self.green),
+            ("blue", self.blue),
+        ];
+        for (i, (name, (x, y))) in points.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(
+                f,
+                "{} ({:.5}, {:.5})",
+                name,
+                *x as f64 / SCALE,
+                *y as f64 / SCALE
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The rendering intent stored in the `sRGB` chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderingIntent {
+    Perceptual = 0,
+    RelativeColorimetric = 1,
+    Saturation = 2,
+    AbsoluteColorimetric = 3,
+}
+
+impl RenderingIntent {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("sRGB").unwrap()
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(RenderingIntent::chunk_type(), vec![*self as u8])
+    }
+}
+
+impl TryFrom<&Chunk> for RenderingIntent {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, RenderingIntent::chunk_type())?;
+        expect_length(chunk, 1)?;
+        match chunk.data()[0] {
+            0 => Ok(RenderingIntent::Perceptual),
+            1 => Ok(RenderingIntent::RelativeColorimetric),
+            2 => Ok(RenderingIntent::Saturation),
+            3 => Ok(RenderingIntent::AbsoluteColorimetric),
+            other => Err(invalid(
+                RenderingIntent::chunk_type(),
+                format!("unknown rendering intent {}", other),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RenderingIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RenderingIntent::Perceptual => "perceptual",
+            RenderingIntent::RelativeColorimetric => "relative colorimetric",
+            RenderingIntent::Saturation => "saturation",
+            RenderingIntent::AbsoluteColorimetric => "absolute colorimetric",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The decoded contents of the `iCCP` chunk: a named ICC profile, stored zlib compressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IccProfile {
+    /// Latin-1, following the same rules as text keywords.
+    pub name: String,
+    /// The uncompressed ICC profile.
+    pub profile: Vec<u8>,
+}
+
+impl IccProfile {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("iCCP").unwrap()
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        let chunk_type = IccProfile::chunk_type();
+        check_keyword(chunk_type, &self.name)?;
+        let mut data = encode_latin1(chunk_type, &self.name)?;
+        data.push(0);
+        data.push(zlib::COMPRESSION_METHOD_ZLIB);
+        data.extend(zlib::compress(&self.profile));
+        Ok(Chunk::new(chunk_type, data))
+    }
+}
+
+impl TryFrom<&Chunk> for IccProfile {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let chunk_type = IccProfile::chunk_type();
+        check_type(chunk, chunk_type)?;
+        let data = chunk.data();
+        let separator = data.iter().position(|&b| b == 0).ok_or_else(|| {
+            invalid(
+                chunk_type,
+                "missing null separator after profile name".to_string(),
+            )
+        })?;
+        let method = *data
+            .get(separator + 1)
+            .ok_or_else(|| invalid(chunk_type, "missing compression method".to_string()))?;
+        zlib::check_method(chunk_type, method)?;
+
+        let name = decode_latin1(&data[..separator]);
+        check_keyword(chunk_type, &name)?;
+        Ok(IccProfile {
+            name,
+            profile: zlib::decompress(chunk_type, &data[separator + 2..])?,
+        })
+    }
+}
+
+/// Every colour-management chunk an image carries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColorInfo {
+    pub gamma: Option<Gamma>,
+    pub chromaticities: Option<Chromaticities>,
+    pub rendering_intent: Option<RenderingIntent>,
+    pub icc_profile: Option<IccProfile>,
+}
+
+impl ColorInfo {
+    /// Chunk types that carry colour-management information.
+    pub const CHUNK_TYPES: [&'static str; 4] = ["gAMA", "cHRM", "sRGB", "iCCP"];
+
+    pub fn is_empty(&self) -> bool {
+        *self == ColorInfo::default()
+    }
+}
+
+impl fmt::Display for ColorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no colour information");
+        }
+        if let Some(gamma) = &self.gamma {
+            writeln!(f, "gamma: {}", gamma)?;
+        }
+        if let Some(chromaticities) = &self.chromaticities {
+            writeln!(f, "chromaticities: {}", chromaticities)?;
+        }
+        if let Some(intent) = &self.rendering_intent {
+            writeln!(f, "sRGB: {}", intent)?;
+        }
+        if let Some(icc) = &self.icc_profile {
+            writeln!(f, "ICC profile: {} ({} bytes)", icc.name, icc.profile.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl Png {
+    /// Decodes the `gAMA`, `cHRM`, `sRGB` and `iCCP` chunks that are present.
+    pub fn color_info(&self) -> Result<ColorInfo> {
+        Ok(ColorInfo {
+            gamma: self
+                .chunk_by_type("gAMA")
+                .map(Gamma::try_from)
+                .transpose()?,
+            chromaticities: self
+                .chunk_by_type("cHRM")
+                .map(Chromaticities::try_from)
+                .transpose()?,
+            rendering_intent: self
+                .chunk_by_type("sRGB")
+                .map(RenderingIntent::try_from)
+                .transpose()?,
+            icc_profile: self
+                .chunk_by_type("iCCP")
+                .map(IccProfile::try_from)
+                .transpose()?,
+        })
+    }
+
+    /// Removes every colour-management chunk, returning them in file order.
+    pub fn strip_color_info(&mut self) -> Vec<Chunk> {
+        self.remove_chunks_where(|chunk| {
+            ColorInfo::CHUNK_TYPES.contains(&chunk.chunk_type().to_string().as_str())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_chromaticities() -> Chromaticities {
+        Chromaticities {
+            white: (31270, 32900),
+            red: (64000, 33000),
+            green: (30000, 60000),
+            blue: (15000, 6000),
+        }
+    }
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Gamma { value: 45455 }.to_chunk(),
+            testing_chromaticities().to_chunk(),
+            IccProfile {
+                name: "Display P3".to_string(),
+                profile: b"fake profile".repeat(20),
+            }
+            .to_chunk()
+            .unwrap(),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_gamma() {
+        let gamma = Gamma { value: 45455 };
+        let chunk = gamma.to_chunk();
+        assert_eq!(Gamma::try_from(&chunk).unwrap(), gamma);
+        assert_eq!(gamma.to_string(), "0.45455");
+
+        let chunk = Chunk::new(Gamma::chunk_type(), vec![0; 4]);
+        assert!(Gamma::try_from(&chunk).is_err());
+        let chunk = Chunk::new(Gamma::chunk_type(), vec![1; 3]);
+        assert!(Gamma::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_chromaticities() {
+        let chrm = testing_chromaticities();
+        let chunk = chrm.to_chunk();
+        assert_eq!(chunk.length(), 32);
+        assert_eq!(&chunk.data()[..4], 31270u32.to_be_bytes());
+        assert_eq!(Chromaticities::try_from(&chunk).unwrap(), chrm);
+        assert!(chrm
+            .to_string()
+            .starts_with("white (0.31270, 0.32900), red"));
+    }
+
+    #[test]
+    fn test_rendering_intent() {
+        let chunk = RenderingIntent::Saturation.to_chunk();
+        assert_eq!(chunk.data(), [2]);
+        assert_eq!(
+            RenderingIntent::try_from(&chunk).unwrap(),
+            RenderingIntent::Saturation
+        );
+        let chunk = Chunk::new(RenderingIntent::chunk_type(), vec![4]);
+        assert!(RenderingIntent::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_icc_profile() {
+        let icc = IccProfile {
+            name: "sRGB IEC61966-2.1".to_string(),
+            profile: vec![1, 2, 3, 4],
+        };
+        let chunk = icc.to_chunk().unwrap();
+        assert!(chunk.data().starts_with(b"sRGB IEC61966-2.1\0\0"));
+        assert_eq!(IccProfile::try_from(&chunk).unwrap(), icc);
+
+        let mut data = b"name\0\x01".to_vec();
+        data.extend(zlib::compress(b"profile"));
+        let chunk = Chunk::new(IccProfile::chunk_type(), data);
+        assert!(matches!(
+            IccProfile::try_from(&chunk),
+            Err(PngError::UnknownCompressionMethod { method: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_color_info() {
+        let mut png = testing_png();
+        let info = png.color_info().unwrap();
+        assert_eq!(info.gamma, Some(Gamma { value: 45455 }));
+        assert_eq!(info.chromaticities, Some(testing_chromaticities()));
+        assert_eq!(info.rendering_intent, None);
+        assert_eq!(info.icc_profile.unwrap().name, "Display P3");
+
+        let removed = png.strip_color_info();
+        assert_eq!(removed.len(), 3);
+        assert!(png.color_info().unwrap().is_empty());
+        assert_eq!(png.chunks().len(), 2);
+    }
+}
@@ -1,36 +1,226 @@
-use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png,};
-use std::io::{self, Error};
-#[derive(Debug)]
+use crate::{
+    ancillary::AncillaryInfo,
+    chunk::Chunk,
+    chunk_type::ChunkType,
+    color::{ColorInfo, IccProfile},
+    exif::{Exif, ExifEntry, TagSelector},
+    ihdr::Ihdr,
+    image::ImageDifference,
+    list::ChunkSummary,
+    palette::{Palette, Transparency},
+    png::Png,
+    text::TextEntry,
+    time::Time,
+    validate::Diagnostic,
+    xmp::DublinCore,
+};
+use clap::{Args, Subcommand};
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Subcommand)]
 pub enum Commands {
+    /// Hide a message in a new chunk
     Encode(EncodeCommand),
+    /// Print the message stored in a chunk
     Decode(DataCommand),
-    Remove(DataCommand),
+    /// Remove a chunk and write the result
+    Remove(RemoveCommand),
+    /// Print the type of a chunk
     Print(DataCommand),
+    /// Show the image header (IHDR), or every typed chunk with --all
+    Info(InfoCommand),
+    /// Check the chunk layout against the PNG spec
+    Validate(FileCommand),
+    /// Check that two images decode to the same pixels
+    Compare(CompareCommand),
+    /// List every chunk with its offset, length and CRC
+    List(ListCommand),
+    /// Print the palette (PLTE) with tRNS alpha values
+    Palette(ListCommand),
+    /// Show, strip or extract colour-management chunks
+    #[command(subcommand)]
+    Color(ColorCommand),
+    /// Read and edit tEXt, zTXt and iTXt metadata
+    #[command(subcommand)]
+    Text(TextCommand),
+    /// Show or remove EXIF tags stored in eXIf
+    #[command(subcommand)]
+    Exif(ExifCommand),
+    /// Read and edit the XMP packet and its Dublin Core properties
+    #[command(subcommand)]
+    Xmp(XmpCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ColorCommand {
+    /// Show gAMA, cHRM, sRGB and iCCP
+    Show(FileCommand),
+    /// Remove every colour-management chunk
+    Strip(StripCommand),
+    /// Write the embedded ICC profile to a file
+    ExtractIcc(ExtractIccCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExifCommand {
+    /// List every EXIF tag
+    Show(FileCommand),
+    /// Remove the eXIf chunk
+    Strip(StripCommand),
+    /// Remove tags, e.g. GPS or BodySerialNumber, and rewrite eXIf
+    RemoveTag(ExifRemoveTagCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum XmpCommand {
+    /// Print the raw XMP packet
+    Show(FileCommand),
+    /// Print a Dublin Core property: creator, rights or description
+    Get(XmpGetCommand),
+    /// Set a Dublin Core property, creating the packet if needed
+    Set(XmpSetCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TextCommand {
+    /// Print the text stored under a keyword
+    Get(TextGetCommand),
+    /// Store text under a keyword, replacing any existing entry
+    Set(TextSetCommand),
+    /// List every text entry
+    List(FileCommand),
+    /// Remove every entry with a keyword
+    Remove(TextRemoveCommand),
 }
 
 #[derive(Debug)]
 pub enum OperationResult {
     EncodedPng(Png),
     DecodedMessage(String),
-    RemovedChunk(Chunk),
+    RemovedChunks(Vec<Chunk>),
     PrintedInfo(Result<(), Error>),
+    HeaderInfo(Ihdr),
+    FullInfo {
+        header: Ihdr,
+        palette: Option<Palette>,
+        color: Box<ColorInfo>,
+        ancillary: Box<AncillaryInfo>,
+        last_modified: Option<Time>,
+        /// Chunks outside `ancillary` that could not be decoded.
+        diagnostics: Vec<Diagnostic>,
+    },
+    Validated(Vec<Diagnostic>),
+    ChunkList {
+        chunks: Vec<ChunkSummary>,
+        json: bool,
+    },
+    PaletteInfo {
+        palette: Palette,
+        transparency: Option<Transparency>,
+        json: bool,
+    },
+    ColorInfo(ColorInfo),
+    ExtractedProfile {
+        path: PathBuf,
+        profile: IccProfile,
+    },
+    TextEntries {
+        entries: Vec<TextEntry>,
+        diagnostics: Vec<Diagnostic>,
+    },
+    ExifTags(Exif),
+    RemovedExifTags(Vec<ExifEntry>),
+    Compared(Option<ImageDifference>),
 }
 
-#[derive(Debug)]
+/// Where a modified image is written. Exactly one must be given.
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+pub struct OutputArgs {
+    /// Write the result to this file
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Overwrite the input file
+    #[arg(short, long)]
+    in_place: bool,
+    /// Write the result to standard output
+    #[arg(long)]
+    stdout: bool,
+}
+
+impl OutputArgs {
+    pub fn new(output: Option<PathBuf>, in_place: bool, stdout: bool) -> OutputArgs {
+        OutputArgs {
+            output,
+            in_place,
+            stdout,
+        }
+    }
+
+    pub fn output(&self) -> Option<&Path> {
+        self.output.as_deref()
+    }
+
+    pub fn in_place(&self) -> bool {
+        self.in_place
+    }
+
+    pub fn stdout(&self) -> bool {
+        self.stdout
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct FileCommand {
+    /// PNG file to read
+    file: PathBuf,
+}
+
+impl FileCommand {
+    pub fn new(file: PathBuf) -> FileCommand {
+        FileCommand { file }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CompareCommand {
+    /// First PNG file, e.g. the original
+    first: PathBuf,
+    /// Second PNG file, e.g. the edited copy
+    second: PathBuf,
+}
+
+impl CompareCommand {
+    pub fn new(first: PathBuf, second: PathBuf) -> CompareCommand {
+        CompareCommand { first, second }
+    }
+    pub fn first(&self) -> &Path {
+        &self.first
+    }
+
+    pub fn second(&self) -> &Path {
+        &self.second
+    }
+}
+
+#[derive(Debug, Args)]
 pub struct DataCommand {
-    data: Vec<u8>,
+    /// PNG file to read
+    file: PathBuf,
+    /// Four letter chunk type, e.g. ruSt
     chunk_type: ChunkType,
 }
 
 impl DataCommand {
-    pub fn new(data: Vec<u8>, chunk_type: ChunkType) -> DataCommand {
-        DataCommand {
-            data: data,
-            chunk_type: chunk_type,
-        }
+    pub fn new(file: PathBuf, chunk_type: ChunkType) -> DataCommand {
+        DataCommand { file, chunk_type }
     }
-    pub fn data(&self) -> &Vec<u8> {
-        &self.data
+    pub fn file(&self) -> &Path {
+        &self.file
     }
 
     pub fn chunk_type(&self) -> &ChunkType {
@@ -38,41 +228,443 @@ impl DataCommand {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Args)]
 pub struct EncodeCommand {
-    data: Vec<u8>,
+    /// PNG file to read
+    file: PathBuf,
+    /// Four letter chunk type, e.g. ruSt
     chunk_type: ChunkType,
+    /// Message to store in the chunk
     message: String,
-    output: String,
+    /// Write the chunk even if its type has the reserved bit set
+    #[arg(long)]
+    force: bool,
+    /// Set the tIME chunk to the current time
+    #[arg(short, long)]
+    touch: bool,
+    #[command(flatten)]
+    destination: OutputArgs,
 }
 
 impl EncodeCommand {
     pub fn new(
-        data: Vec<u8>,
+        file: PathBuf,
         chunk_type: ChunkType,
         message: String,
-        output: String,
+        force: bool,
+        touch: bool,
+        destination: OutputArgs,
     ) -> EncodeCommand {
         EncodeCommand {
-            data,
+            file,
             chunk_type,
             message,
-            output,
+            force,
+            touch,
+            destination,
         }
     }
     pub fn message(&self) -> &String {
         &self.message
     }
 
-    pub fn output(&self) -> &String {
-        &self.output
+    /// Write the chunk even if its type has the reserved bit set.
+    pub fn force(&self) -> bool {
+        self.force
     }
 
-    pub fn data(&self) -> &Vec<u8> {
-        &self.data
+    /// Set the tIME chunk to the current time.
+    pub fn touch(&self) -> bool {
+        self.touch
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
     }
 
     pub fn chunk_type(&self) -> &ChunkType {
         &self.chunk_type
     }
+
+    pub fn destination(&self) -> &OutputArgs {
+        &self.destination
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct InfoCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Also show palette, colour, physical and other ancillary chunks
+    #[arg(short, long)]
+    all: bool,
+}
+
+impl InfoCommand {
+    pub fn new(file: PathBuf, all: bool) -> InfoCommand {
+        InfoCommand { file, all }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Show every typed chunk, not only the header.
+    pub fn all(&self) -> bool {
+        self.all
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ListCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Print the listing as JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+impl ListCommand {
+    pub fn new(file: PathBuf, json: bool) -> ListCommand {
+        ListCommand { file, json }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// Print the listing as JSON instead of a table.
+    pub fn json(&self) -> bool {
+        self.json
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct RemoveCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Four letter chunk type, e.g. ruSt
+    chunk_type: ChunkType,
+    /// Remove every chunk of this type instead of only the first
+    #[arg(short, long)]
+    all: bool,
+    /// Set the tIME chunk to the current time
+    #[arg(short, long)]
+    touch: bool,
+    #[command(flatten)]
+    destination: OutputArgs,
+}
+
+impl RemoveCommand {
+    pub fn new(
+        file: PathBuf,
+        chunk_type: ChunkType,
+        all: bool,
+        touch: bool,
+        destination: OutputArgs,
+    ) -> RemoveCommand {
+        RemoveCommand {
+            file,
+            chunk_type,
+            all,
+            touch,
+            destination,
+        }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn chunk_type(&self) -> &ChunkType {
+        &self.chunk_type
+    }
+
+    /// Remove every matching chunk instead of only the first.
+    pub fn all(&self) -> bool {
+        self.all
+    }
+
+    /// Set the tIME chunk to the current time.
+    pub fn touch(&self) -> bool {
+        self.touch
+    }
+
+    pub fn destination(&self) -> &OutputArgs {
+        &self.destination
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct TextGetCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Keyword to look up, e.g. Title
+    keyword: String,
+}
+
+impl TextGetCommand {
+    pub fn new(file: PathBuf, keyword: String) -> TextGetCommand {
+        TextGetCommand { file, keyword }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct TextSetCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Keyword of 1 to 79 Latin-1 characters, e.g. Title
+    keyword: String,
+    /// Text to store: Latin-1, or any UTF-8 with --international
+    text: String,
+    /// Store the text zlib compressed (zTXt, or compressed iTXt)
+    #[arg(short = 'z', long)]
+    compress: bool,
+    /// Store UTF-8 text in an iTXt chunk instead of Latin-1
+    #[arg(short = 'u', long)]
+    international: bool,
+    /// Language of the text, e.g. en-GB (iTXt only)
+    #[arg(long, requires = "international")]
+    lang: Option<String>,
+    /// The keyword translated into the text's language (iTXt only)
+    #[arg(long, requires = "international")]
+    translated: Option<String>,
+    #[command(flatten)]
+    destination: OutputArgs,
+}
+
+impl TextSetCommand {
+    pub fn new(
+        file: PathBuf,
+        keyword: String,
+        text: String,
+        compress: bool,
+        international: bool,
+        destination: OutputArgs,
+    ) -> TextSetCommand {
+        TextSetCommand {
+            file,
+            keyword,
+            text,
+            compress,
+            international,
+            lang: None,
+            translated: None,
+            destination,
+        }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Store the text in a zTXt chunk instead of tEXt.
+    pub fn compress(&self) -> bool {
+        self.compress
+    }
+
+    /// Store the text in an iTXt chunk.
+    pub fn international(&self) -> bool {
+        self.international
+    }
+
+    pub fn lang(&self) -> Option<&str> {
+        self.lang.as_deref()
+    }
+
+    pub fn translated(&self) -> Option<&str> {
+        self.translated.as_deref()
+    }
+
+    pub fn destination(&self) -> &OutputArgs {
+        &self.destination
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct TextRemoveCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Keyword to remove, e.g. Title
+    keyword: String,
+    #[command(flatten)]
+    destination: OutputArgs,
+}
+
+impl TextRemoveCommand {
+    pub fn new(file: PathBuf, keyword: String, destination: OutputArgs) -> TextRemoveCommand {
+        TextRemoveCommand {
+            file,
+            keyword,
+            destination,
+        }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn destination(&self) -> &OutputArgs {
+        &self.destination
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct StripCommand {
+    /// PNG file to read
+    file: PathBuf,
+    #[command(flatten)]
+    destination: OutputArgs,
+}
+
+impl StripCommand {
+    pub fn new(file: PathBuf, destination: OutputArgs) -> StripCommand {
+        StripCommand { file, destination }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn destination(&self) -> &OutputArgs {
+        &self.destination
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ExtractIccCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Where to write the profile [default: the input with an .icc extension]
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl ExtractIccCommand {
+    pub fn new(file: PathBuf, output: Option<PathBuf>) -> ExtractIccCommand {
+        ExtractIccCommand { file, output }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// The profile path, defaulting to the input path with an `.icc` extension.
+    pub fn output(&self) -> PathBuf {
+        self.output
+            .clone()
+            .unwrap_or_else(|| self.file.with_extension("icc"))
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ExifRemoveTagCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Tags to remove: a name, a number such as 0xa431, GPS or thumbnail
+    #[arg(required = true)]
+    tags: Vec<TagSelector>,
+    #[command(flatten)]
+    destination: OutputArgs,
+}
+
+impl ExifRemoveTagCommand {
+    pub fn new(
+        file: PathBuf,
+        tags: Vec<TagSelector>,
+        destination: OutputArgs,
+    ) -> ExifRemoveTagCommand {
+        ExifRemoveTagCommand {
+            file,
+            tags,
+            destination,
+        }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn tags(&self) -> &[TagSelector] {
+        &self.tags
+    }
+
+    pub fn destination(&self) -> &OutputArgs {
+        &self.destination
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct XmpGetCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Property to print: creator, rights or description
+    property: DublinCore,
+}
+
+impl XmpGetCommand {
+    pub fn new(file: PathBuf, property: DublinCore) -> XmpGetCommand {
+        XmpGetCommand { file, property }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn property(&self) -> DublinCore {
+        self.property
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct XmpSetCommand {
+    /// PNG file to read
+    file: PathBuf,
+    /// Property to set: creator, rights or description
+    property: DublinCore,
+    /// New value of the property
+    value: String,
+    #[command(flatten)]
+    destination: OutputArgs,
+}
+
+impl XmpSetCommand {
+    pub fn new(
+        file: PathBuf,
+        property: DublinCore,
+        value: String,
+        destination: OutputArgs,
+    ) -> XmpSetCommand {
+        XmpSetCommand {
+            file,
+            property,
+            value,
+            destination,
+        }
+    }
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn property(&self) -> DublinCore {
+        self.property
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn destination(&self) -> &OutputArgs {
+        &self.destination
+    }
 }
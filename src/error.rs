@@ -0,0 +1,193 @@
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+
+use crate::chunk_type::ChunkType;
+
+pub type Result<T> = std::result::Result<T, PngError>;
+
+/// Every way reading, editing or writing a PNG can fail.
+#[derive(Debug)]
+pub enum PngError {
+    /// The input does not start with the 8 byte PNG signature.
+    InvalidSignature(Vec<u8>),
+    /// The input ended before a complete chunk could be read. `offset` is where
+    /// the chunk starts in the input and `chunk_index` its position in the file.
+    TruncatedChunk {
+        chunk_index: usize,
+        offset: usize,
+        needed: usize,
+        available: usize,
+    },
+    /// Bytes were left over after the end of a single chunk.
+    TrailingBytes(usize),
+    /// A chunk declares a length larger than the PNG spec allows (2^31 - 1)
+    /// or larger than the bytes remaining after its header. `remaining` is
+    /// `None` when reading from a stream of unknown length.
+    LengthOverflow {
+        chunk_index: usize,
+        offset: usize,
+        length: u32,
+        remaining: Option<usize>,
+    },
+    /// The CRC stored in the file does not match the chunk contents.
+    CrcMismatch {
+        chunk_type: ChunkType,
+        expected: u32,
+        actual: u32,
+    },
+    /// The chunk type is not four ASCII letters.
+    InvalidChunkType([u8; 4]),
+    /// Refused to write a chunk whose type has the reserved bit set.
+    ReservedBitSet(ChunkType),
+    /// A chunk type string was not exactly four characters long.
+    InvalidChunkTypeLength(usize),
+    /// A chunk's contents do not follow the layout the PNG spec defines for its type.
+    InvalidChunkData {
+        chunk_type: ChunkType,
+        reason: String,
+    },
+    /// A compressed chunk uses a compression method other than zlib (0).
+    UnknownCompressionMethod {
+        chunk_type: ChunkType,
+        method: u8,
+    },
+    /// A compressed chunk inflates to more than `limit` bytes, the most pngme
+    /// will decompress for a single text, profile or metadata chunk.
+    DecompressedTooLarge {
+        chunk_type: ChunkType,
+        limit: usize,
+    },
+    /// The zlib stream formed by the `IDAT` chunks is corrupt, incomplete or
+    /// does not hold the amount of scanline data the header describes.
+    CorruptImageData(String),
+    /// The Adler-32 checksum ending the image data does not match the inflated bytes.
+    Adler32Mismatch {
+        expected: u32,
+        actual: u32,
+    },
+    /// A chunk index past the end of the image.
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+    /// No chunk of the requested type exists in the image.
+    ChunkNotFound(String),
+    /// The chunk data is not valid UTF-8.
+    InvalidUtf8(FromUtf8Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngError::InvalidSignature(found) => {
+                write!(f, "invalid PNG signature {:?}", found)
+            }
+            PngError::TruncatedChunk {
+                chunk_index,
+                offset,
+                needed,
+                available,
+            } => write!(
+                f,
+                "truncated chunk #{} at offset {}: needed {} bytes but only {} available",
+                chunk_index, offset, needed, available
+            ),
+            PngError::TrailingBytes(extra) => {
+                write!(f, "{} unexpected bytes after the end of the chunk", extra)
+            }
+            PngError::LengthOverflow {
+                chunk_index,
+                offset,
+                length,
+                remaining: Some(remaining),
+            } => write!(
+                f,
+                "chunk #{} at offset {} declares length {} but only {} bytes remain",
+                chunk_index, offset, length, remaining
+            ),
+            PngError::LengthOverflow {
+                chunk_index,
+                offset,
+                length,
+                remaining: None,
+            } => write!(
+                f,
+                "chunk #{} at offset {} declares length {} which exceeds the maximum of 2^31 - 1",
+                chunk_index, offset, length
+            ),
+            PngError::CrcMismatch {
+                chunk_type,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "CRC mismatch in chunk {}: expected {:#010x}, found {:#010x}",
+                chunk_type, expected, actual
+            ),
+            PngError::InvalidChunkType(bytes) => {
+                write!(f, "invalid chunk type bytes {:?}", bytes)
+            }
+            PngError::ReservedBitSet(chunk_type) => write!(
+                f,
+                "chunk type {} has the reserved bit set (third letter must be uppercase)",
+                chunk_type
+            ),
+            PngError::InvalidChunkTypeLength(len) => {
+                write!(f, "chunk type must be 4 characters, got {}", len)
+            }
+            PngError::InvalidChunkData { chunk_type, reason } => {
+                write!(f, "invalid {} chunk: {}", chunk_type, reason)
+            }
+            PngError::UnknownCompressionMethod { chunk_type, method } => write!(
+                f,
+                "unknown compression method {} in {} chunk",
+                method, chunk_type
+            ),
+            PngError::DecompressedTooLarge { chunk_type, limit } => write!(
+                f,
+                "{} chunk inflates to more than {} bytes",
+                chunk_type, limit
+            ),
+            PngError::CorruptImageData(reason) => write!(f, "corrupt image data: {}", reason),
+            PngError::Adler32Mismatch { expected, actual } => write!(
+                f,
+                "Adler-32 mismatch in image data: expected {:#010x}, found {:#010x}",
+                expected, actual
+            ),
+            PngError::IndexOutOfRange { index, len } => {
+                write!(
+                    f,
+                    "chunk index {} is out of range for {} chunks",
+                    index, len
+                )
+            }
+            PngError::ChunkNotFound(chunk_type) => write!(f, "chunk type {} not found", chunk_type),
+            PngError::InvalidUtf8(e) => write!(f, "chunk data is not valid UTF-8: {}", e),
+            PngError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PngError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PngError::InvalidUtf8(e) => Some(e),
+            PngError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PngError {
+    fn from(e: io::Error) -> Self {
+        PngError::Io(e)
+    }
+}
+
+impl From<FromUtf8Error> for PngError {
+    fn from(e: FromUtf8Error) -> Self {
+        PngError::InvalidUtf8(e)
+    }
+}
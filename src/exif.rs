@@ -0,0 +1,866 @@
+use std::collections::HashSet;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_data::{check_type, invalid};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::png::Png;
+
+const EXIF_IFD_POINTER: u16 = 0x8769;
+const GPS_IFD_POINTER: u16 = 0x8825;
+const INTEROP_IFD_POINTER: u16 = 0xa005;
+const THUMBNAIL_OFFSET: u16 = 0x0201;
+const THUMBNAIL_LENGTH: u16 = 0x0202;
+
+/// Guards against corrupt files declaring absurd entry counts.
+const MAX_ENTRIES_PER_IFD: usize = 4096;
+
+/// Byte order of the TIFF structure, from its `II` or `MM` header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    fn u16(self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        match self {
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+        }
+    }
+
+    fn u32(self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        match self {
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+        }
+    }
+
+    fn u64(self, bytes: &[u8]) -> u64 {
+        let bytes: [u8; 8] = bytes[..8].try_into().unwrap();
+        match self {
+            ByteOrder::LittleEndian => u64::from_le_bytes(bytes),
+            ByteOrder::BigEndian => u64::from_be_bytes(bytes),
+        }
+    }
+
+    fn put_u16(self, out: &mut Vec<u8>, value: u16) {
+        match self {
+            ByteOrder::LittleEndian => out.extend_from_slice(&value.to_le_bytes()),
+            ByteOrder::BigEndian => out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    fn put_u32(self, out: &mut Vec<u8>, value: u32) {
+        match self {
+            ByteOrder::LittleEndian => out.extend_from_slice(&value.to_le_bytes()),
+            ByteOrder::BigEndian => out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    fn put_u64(self, out: &mut Vec<u8>, value: u64) {
+        match self {
+            ByteOrder::LittleEndian => out.extend_from_slice(&value.to_le_bytes()),
+            ByteOrder::BigEndian => out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+}
+
+/// Which image file directory an entry lives in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Ifd {
+    /// IFD0, describing the main image.
+    Primary,
+    Exif,
+    Gps,
+    Interop,
+    /// IFD1, describing the embedded thumbnail.
+    Thumbnail,
+}
+
+impl fmt::Display for Ifd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Ifd::Primary => "IFD0",
+            Ifd::Exif => "Exif",
+            Ifd::Gps => "GPS",
+            Ifd::Interop => "Interop",
+            Ifd::Thumbnail => "IFD1",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A decoded TIFF field value, one variant per TIFF field type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExifValue {
+    Byte(Vec<u8>),
+    /// Usually null terminated.
+    Ascii(Vec<u8>),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SShort(Vec<i16>),
+    SLong(Vec<i32>),
+    SRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl ExifValue {
+    /// Size in bytes of one value of TIFF field type `field_type`.
+    fn unit_size(field_type: u16) -> Option<usize> {
+        match field_type {
+            1 | 2 | 6 | 7 => Some(1),
+            3 | 8 => Some(2),
+            4 | 9 | 11 => Some(4),
+            5 | 10 | 12 => Some(8),
+            _ => None,
+        }
+    }
+
+    fn field_type(&self) -> u16 {
+        match self {
+            ExifValue::Byte(_) => 1,
+            ExifValue::Ascii(_) => 2,
+            ExifValue::Short(_) => 3,
+            ExifValue::Long(_) => 4,
+            ExifValue::Rational(_) => 5,
+            ExifValue::SByte(_) => 6,
+            ExifValue::Undefined(_) => 7,
+            ExifValue::SShort(_) => 8,
+            ExifValue::SLong(_) => 9,
+            ExifValue::SRational(_) => 10,
+            ExifValue::Float(_) => 11,
+            ExifValue::Double(_) => 12,
+        }
+    }
+
+    /// Number of values, as stored in the entry's count field.
+    pub fn count(&self) -> usize {
+        match self {
+            ExifValue::Byte(v) | ExifValue::Ascii(v) | ExifValue::Undefined(v) => v.len(),
+            ExifValue::Short(v) => v.len(),
+            ExifValue::Long(v) => v.len(),
+            ExifValue::Rational(v) => v.len(),
+            ExifValue::SByte(v) => v.len(),
+            ExifValue::SShort(v) => v.len(),
+            ExifValue::SLong(v) => v.len(),
+            ExifValue::SRational(v) => v.len(),
+            ExifValue::Float(v) => v.len(),
+            ExifValue::Double(v) => v.len(),
+        }
+    }
+
+    fn decode(field_type: u16, bytes: &[u8], order: ByteOrder) -> ExifValue {
+        let units = |size: usize| bytes.chunks_exact(size);
+        match field_type {
+            1 => ExifValue::Byte(bytes.to_vec()),
+            2 => ExifValue::Ascii(bytes.to_vec()),
+            3 => ExifValue::Short(units(2).map(|b| order.u16(b)).collect()),
+            4 => ExifValue::Long(units(4).map(|b| order.u32(b)).collect()),
+            5 => ExifValue::Rational(
+                units(8)
+                    .map(|b| (order.u32(b), order.u32(&b[4..])))
+                    .collect(),
+            ),
+            6 => ExifValue::SByte(bytes.iter().map(|&b| b as i8).collect()),
+            8 => ExifValue::SShort(units(2).map(|b| order.u16(b) as i16).collect()),
+            9 => ExifValue::SLong(units(4).map(|b| order.u32(b) as i32).collect()),
+            10 => ExifValue::SRational(
+                units(8)
+                    .map(|b| (order.u32(b) as i32, order.u32(&b[4..]) as i32))
+                    .collect(),
+            ),
+            11 => ExifValue::Float(units(4).map(|b| f32::from_bits(order.u32(b))).collect()),
+            12 => ExifValue::Double(units(8).map(|b| f64::from_bits(order.u64(b))).collect()),
+            _ => ExifValue::Undefined(bytes.to_vec()),
+        }
+    }
+
+    fn encode(&self, order: ByteOrder) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            ExifValue::Byte(v) | ExifValue::Ascii(v) | ExifValue::Undefined(v) => {
+                out.extend_from_slice(v)
+            }
+            ExifValue::SByte(v) => out.extend(v.iter().map(|&b| b as u8)),
+            ExifValue::Short(v) => v.iter().for_each(|&x| order.put_u16(&mut out, x)),
+            ExifValue::SShort(v) => v.iter().for_each(|&x| order.put_u16(&mut out, x as u16)),
+            ExifValue::Long(v) => v.iter().for_each(|&x| order.put_u32(&mut out, x)),
+            ExifValue::SLong(v) => v.iter().for_each(|&x| order.put_u32(&mut out, x as u32)),
+            ExifValue::Rational(v) => v.iter().for_each(|&(n, d)| {
+                order.put_u32(&mut out, n);
+                order.put_u32(&mut out, d);
+            }),
+            ExifValue::SRational(v) => v.iter().for_each(|&(n, d)| {
+                order.put_u32(&mut out, n as u32);
+                order.put_u32(&mut out, d as u32);
+            }),
+            ExifValue::Float(v) => v.iter().for_each(|&x| order.put_u32(&mut out, x.to_bits())),
+            ExifValue::Double(v) => v.iter().for_each(|&x| order.put_u64(&mut out, x.to_bits())),
+        }
+        out
+    }
+}
+
+/// Joins at most the first 16 values, marking the rest as elided.
+fn join<T: fmt::Display>(values: &[T]) -> String {
+    let mut out: Vec<String> = values.iter().take(16).map(T::to_string).collect();
+    if values.len() > 16 {
+        out.push(format!("... ({} values)", values.len()));
+    }
+    out.join(", ")
+}
+
+impl fmt::Display for ExifValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExifValue::Ascii(v) => {
+                let text = String::from_utf8_lossy(v);
+                write!(f, "{}", text.trim_end_matches('\0'))
+            }
+            ExifValue::Byte(v) | ExifValue::Undefined(v) if v.len() > 16 => {
+                write!(f, "<{} bytes>", v.len())
+            }
+            ExifValue::Byte(v) | ExifValue::Undefined(v) => {
+                let hex: Vec<String> = v.iter().map(|b| format!("{:02x}", b)).collect();
+                write!(f, "{}", hex.join(" "))
+            }
+            ExifValue::Short(v) => write!(f, "{}", join(v)),
+            ExifValue::Long(v) => write!(f, "{}", join(v)),
+            ExifValue::SByte(v) => write!(f, "{}", join(v)),
+            ExifValue::SShort(v) => write!(f, "{}", join(v)),
+            ExifValue::SLong(v) => write!(f, "{}", join(v)),
+            ExifValue::Float(v) => write!(f, "{}", join(v)),
+            ExifValue::Double(v) => write!(f, "{}", join(v)),
+            ExifValue::Rational(v) => {
+                let v: Vec<String> = v.iter().map(|(n, d)| format!("{}/{}", n, d)).collect();
+                write!(f, "{}", join(&v))
+            }
+            ExifValue::SRational(v) => {
+                let v: Vec<String> = v.iter().map(|(n, d)| format!("{}/{}", n, d)).collect();
+                write!(f, "{}", join(&v))
+            }
+        }
+    }
+}
+
+/// Tags pngme knows by name, per directory.
+const TAG_NAMES: &[(Ifd, u16, &str)] = &[
+    (Ifd::Primary, 0x010e, "ImageDescription"),
+    (Ifd::Primary, 0x010f, "Make"),
+    (Ifd::Primary, 0x0110, "Model"),
+    (Ifd::Primary, 0x0112, "Orientation"),
+    (Ifd::Primary, 0x011a, "XResolution"),
+    (Ifd::Primary, 0x011b, "YResolution"),
+    (Ifd::Primary, 0x0128, "ResolutionUnit"),
+    (Ifd::Primary, 0x0131, "Software"),
+    (Ifd::Primary, 0x0132, "DateTime"),
+    (Ifd::Primary, 0x013b, "Artist"),
+    (Ifd::Primary, 0x8298, "Copyright"),
+    (Ifd::Primary, 0xc62f, "CameraSerialNumber"),
+    (Ifd::Exif, 0x829a, "ExposureTime"),
+    (Ifd::Exif, 0x829d, "FNumber"),
+    (Ifd::Exif, 0x8827, "ISOSpeedRatings"),
+    (Ifd::Exif, 0x9000, "ExifVersion"),
+    (Ifd::Exif, 0x9003, "DateTimeOriginal"),
+    (Ifd::Exif, 0x9004, "DateTimeDigitized"),
+    (Ifd::Exif, 0x9010, "OffsetTime"),
+    (Ifd::Exif, 0x920a, "FocalLength"),
+    (Ifd::Exif, 0x927c, "MakerNote"),
+    (Ifd::Exif, 0x9286, "UserComment"),
+    (Ifd::Exif, 0xa002, "PixelXDimension"),
+    (Ifd::Exif, 0xa003, "PixelYDimension"),
+    (Ifd::Exif, 0xa420, "ImageUniqueID"),
+    (Ifd::Exif, 0xa430, "CameraOwnerName"),
+    (Ifd::Exif, 0xa431, "BodySerialNumber"),
+    (Ifd::Exif, 0xa433, "LensMake"),
+    (Ifd::Exif, 0xa434, "LensModel"),
+    (Ifd::Exif, 0xa435, "LensSerialNumber"),
+    (Ifd::Gps, 0x0000, "GPSVersionID"),
+    (Ifd::Gps, 0x0001, "GPSLatitudeRef"),
+    (Ifd::Gps, 0x0002, "GPSLatitude"),
+    (Ifd::Gps, 0x0003, "GPSLongitudeRef"),
+    (Ifd::Gps, 0x0004, "GPSLongitude"),
+    (Ifd::Gps, 0x0005, "GPSAltitudeRef"),
+    (Ifd::Gps, 0x0006, "GPSAltitude"),
+    (Ifd::Gps, 0x0007, "GPSTimeStamp"),
+    (Ifd::Gps, 0x001d, "GPSDateStamp"),
+    (Ifd::Interop, 0x0001, "InteroperabilityIndex"),
+];
+
+/// The name of `tag` in directory `ifd`, if pngme knows it.
+pub fn tag_name(ifd: Ifd, tag: u16) -> Option<&'static str> {
+    TAG_NAMES
+        .iter()
+        .find(|&&(i, t, _)| i == ifd && t == tag)
+        .map(|&(_, _, name)| name)
+}
+
+/// One field of an IFD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExifEntry {
+    pub ifd: Ifd,
+    pub tag: u16,
+    pub value: ExifValue,
+}
+
+impl ExifEntry {
+    pub fn name(&self) -> Option<&'static str> {
+        tag_name(self.ifd, self.tag)
+    }
+}
+
+/// Which entries `Exif::remove` drops.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TagSelector {
+    /// Every entry of a directory, e.g. all GPS data.
+    Ifd(Ifd),
+    /// A tag in a given directory, or in any directory.
+    Tag(Option<Ifd>, u16),
+}
+
+impl FromStr for TagSelector {
+    type Err = PngError;
+
+    /// Accepts `GPS` or `thumbnail` for a whole directory, a tag name such as
+    /// `BodySerialNumber`, or a tag number such as `0xa431` or `42033`.
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("gps") {
+            return Ok(TagSelector::Ifd(Ifd::Gps));
+        }
+        if s.eq_ignore_ascii_case("thumbnail") {
+            return Ok(TagSelector::Ifd(Ifd::Thumbnail));
+        }
+        if let Some(&(ifd, tag, _)) = TAG_NAMES
+            .iter()
+            .find(|(_, _, name)| name.eq_ignore_ascii_case(s))
+        {
+            return Ok(TagSelector::Tag(Some(ifd), tag));
+        }
+        let number = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u16::from_str_radix(hex, 16),
+            None => s.parse(),
+        };
+        number
+            .map(|tag| TagSelector::Tag(None, tag))
+            .map_err(|_| invalid(Exif::chunk_type(), format!("unknown EXIF tag {:?}", s)))
+    }
+}
+
+/// The decoded contents of the `eXIf` chunk: a TIFF structure holding the
+/// primary, Exif, GPS, interoperability and thumbnail directories.
+///
+/// The sub-directory pointers and the JPEG thumbnail are rebuilt when the
+/// chunk is written. Other offsets stored inside values, such as those in
+/// maker notes, are copied as they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exif {
+    pub byte_order: ByteOrder,
+    /// Entries grouped by directory, each directory in file order.
+    pub entries: Vec<ExifEntry>,
+    /// JPEG thumbnail referenced from IFD1.
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+/// Reads TIFF directories, following sub-directory pointers.
+struct Parser<'a> {
+    data: &'a [u8],
+    order: ByteOrder,
+    visited: HashSet<usize>,
+    entries: Vec<ExifEntry>,
+    thumbnail: Option<Vec<u8>>,
+}
+
+impl<'a> Parser<'a> {
+    fn slice(&self, offset: usize, len: usize) -> Result<&'a [u8]> {
+        let data = self.data;
+        offset
+            .checked_add(len)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| {
+                invalid(
+                    Exif::chunk_type(),
+                    format!(
+                        "{} bytes at offset {} run past the end of the data",
+                        len, offset
+                    ),
+                )
+            })
+    }
+
+    /// Reads the directory at `offset`, returning the offset of the next one.
+    fn read_ifd(&mut self, ifd: Ifd, offset: usize) -> Result<usize> {
+        if !self.visited.insert(offset) {
+            return Err(invalid(
+                Exif::chunk_type(),
+                format!("directory at offset {} is read twice", offset),
+            ));
+        }
+        let order = self.order;
+        let count = order.u16(self.slice(offset, 2)?) as usize;
+        if count > MAX_ENTRIES_PER_IFD {
+            return Err(invalid(
+                Exif::chunk_type(),
+                format!("{} directory has {} entries", ifd, count),
+            ));
+        }
+        let table = self.slice(offset + 2, count * 12 + 4)?;
+        let next = order.u32(&table[count * 12..]) as usize;
+
+        let mut thumbnail = (None, None);
+        let mut children = Vec::new();
+        for raw in table[..count * 12].chunks_exact(12) {
+            let tag = order.u16(raw);
+            let field_type = order.u16(&raw[2..]);
+            let count = order.u32(&raw[4..]) as usize;
+            let unit = ExifValue::unit_size(field_type).ok_or_else(|| {
+                invalid(
+                    Exif::chunk_type(),
+                    format!("tag {:#06x} has unknown type {}", tag, field_type),
+                )
+            })?;
+            let size = unit.checked_mul(count).ok_or_else(|| {
+                invalid(Exif::chunk_type(), format!("tag {:#06x} is too large", tag))
+            })?;
+            let bytes = if size <= 4 {
+                &raw[8..8 + size]
+            } else {
+                self.slice(order.u32(&raw[8..]) as usize, size)?
+            };
+            let value = ExifValue::decode(field_type, bytes, order);
+            let pointer = || order.u32(&raw[8..]) as usize;
+
+            match (ifd, tag) {
+                (Ifd::Primary, EXIF_IFD_POINTER) => children.push((Ifd::Exif, pointer())),
+                (Ifd::Primary, GPS_IFD_POINTER) => children.push((Ifd::Gps, pointer())),
+                (Ifd::Exif, INTEROP_IFD_POINTER) => children.push((Ifd::Interop, pointer())),
+                (Ifd::Thumbnail, THUMBNAIL_OFFSET) => thumbnail.0 = Some(pointer()),
+                (Ifd::Thumbnail, THUMBNAIL_LENGTH) => thumbnail.1 = Some(pointer()),
+                _ => self.entries.push(ExifEntry { ifd, tag, value }),
+            }
+        }
+        // Sub-directories follow their parent so entries stay grouped.
+        for (child, offset) in children {
+            self.read_ifd(child, offset)?;
+        }
+        if let (Some(offset), Some(len)) = thumbnail {
+            self.thumbnail = Some(self.slice(offset, len)?.to_vec());
+        }
+        Ok(next)
+    }
+}
+
+/// A directory laid out for writing: `(tag, type, count, value bytes)`.
+type RawIfd = Vec<(u16, u16, u32, Vec<u8>)>;
+
+fn ifd_size(entries: &RawIfd) -> usize {
+    let values: usize = entries
+        .iter()
+        .filter(|(_, _, _, bytes)| bytes.len() > 4)
+        .map(|(_, _, _, bytes)| bytes.len() + bytes.len() % 2)
+        .sum();
+    2 + entries.len() * 12 + 4 + values
+}
+
+impl Exif {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("eXIf").unwrap()
+    }
+
+    /// Parses a TIFF structure as stored in the `eXIf` chunk.
+    pub fn parse(data: &[u8]) -> Result<Exif> {
+        let order = match data.get(..4) {
+            Some(b"II*\0") => ByteOrder::LittleEndian,
+            Some(b"MM\0*") => ByteOrder::BigEndian,
+            _ => {
+                return Err(invalid(
+                    Exif::chunk_type(),
+                    "missing TIFF header".to_string(),
+                ))
+            }
+        };
+        let mut parser = Parser {
+            data,
+            order,
+            visited: HashSet::new(),
+            entries: Vec::new(),
+            thumbnail: None,
+        };
+        let first = order.u32(parser.slice(4, 4)?) as usize;
+        let next = parser.read_ifd(Ifd::Primary, first)?;
+        if next != 0 {
+            parser.read_ifd(Ifd::Thumbnail, next)?;
+        }
+        Ok(Exif {
+            byte_order: order,
+            entries: parser.entries,
+            thumbnail: parser.thumbnail,
+        })
+    }
+
+    pub fn entries_in(&self, ifd: Ifd) -> impl Iterator<Item = &ExifEntry> {
+        self.entries.iter().filter(move |entry| entry.ifd == ifd)
+    }
+
+    /// Removes the entries `selector` matches, returning them.
+    pub fn remove(&mut self, selector: TagSelector) -> Vec<ExifEntry> {
+        let matches = |entry: &ExifEntry| match selector {
+            TagSelector::Ifd(ifd) => entry.ifd == ifd,
+            TagSelector::Tag(ifd, tag) => entry.tag == tag && ifd.is_none_or(|i| i == entry.ifd),
+        };
+        let (removed, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| matches(entry));
+        self.entries = kept;
+        if selector == TagSelector::Ifd(Ifd::Thumbnail) {
+            self.thumbnail = None;
+        }
+        removed
+    }
+
+    fn raw_ifd(&self, ifd: Ifd) -> RawIfd {
+        let mut raw: RawIfd = self
+            .entries_in(ifd)
+            .map(|entry| {
+                (
+                    entry.tag,
+                    entry.value.field_type(),
+                    entry.value.count() as u32,
+                    entry.value.encode(self.byte_order),
+                )
+            })
+            .collect();
+        // Pointers are filled in once every directory has an offset.
+        let pointer = |tag: u16| (tag, 4, 1, vec![0; 4]);
+        match ifd {
+            Ifd::Primary => {
+                if self.has_ifd(Ifd::Exif) || self.has_ifd(Ifd::Interop) {
+                    raw.push(pointer(EXIF_IFD_POINTER));
+                }
+                if self.has_ifd(Ifd::Gps) {
+                    raw.push(pointer(GPS_IFD_POINTER));
+                }
+            }
+            Ifd::Exif if self.has_ifd(Ifd::Interop) => raw.push(pointer(INTEROP_IFD_POINTER)),
+            Ifd::Thumbnail if self.thumbnail.is_some() => {
+                raw.push(pointer(THUMBNAIL_OFFSET));
+                raw.push(pointer(THUMBNAIL_LENGTH));
+            }
+            _ => {}
+        }
+        // TIFF requires entries sorted by tag.
+        raw.sort_by_key(|&(tag, ..)| tag);
+        raw
+    }
+
+    fn has_ifd(&self, ifd: Ifd) -> bool {
+        self.entries_in(ifd).next().is_some() || (ifd == Ifd::Thumbnail && self.thumbnail.is_some())
+    }
+
+    /// Serialises the TIFF structure, laying out every directory afresh.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let order = self.byte_order;
+        let kinds: Vec<Ifd> = [
+            Ifd::Primary,
+            Ifd::Exif,
+            Ifd::Interop,
+            Ifd::Gps,
+            Ifd::Thumbnail,
+        ]
+        .into_iter()
+        .filter(|&ifd| {
+            ifd == Ifd::Primary
+                || self.has_ifd(ifd)
+                || (ifd == Ifd::Exif && self.has_ifd(Ifd::Interop))
+        })
+        .collect();
+        let mut ifds: Vec<(Ifd, RawIfd)> =
+            kinds.iter().map(|&ifd| (ifd, self.raw_ifd(ifd))).collect();
+
+        let mut offsets = Vec::new();
+        let mut offset = 8;
+        for (ifd, raw) in &ifds {
+            offsets.push((*ifd, offset));
+            offset += ifd_size(raw);
+        }
+        let thumbnail_offset = offset;
+        let offset_of = |ifd: Ifd| {
+            offsets
+                .iter()
+                .find(|(i, _)| *i == ifd)
+                .map_or(0, |&(_, offset)| offset as u32)
+        };
+
+        for (_, raw) in &mut ifds {
+            for (tag, _, _, bytes) in raw.iter_mut() {
+                let value = match *tag {
+                    EXIF_IFD_POINTER => offset_of(Ifd::Exif),
+                    GPS_IFD_POINTER => offset_of(Ifd::Gps),
+                    INTEROP_IFD_POINTER => offset_of(Ifd::Interop),
+                    THUMBNAIL_OFFSET if self.thumbnail.is_some() => thumbnail_offset as u32,
+                    THUMBNAIL_LENGTH if self.thumbnail.is_some() => {
+                        self.thumbnail.as_ref().map_or(0, |t| t.len() as u32)
+                    }
+                    _ => continue,
+                };
+                bytes.clear();
+                order.put_u32(bytes, value);
+            }
+        }
+
+        let mut out = match order {
+            ByteOrder::LittleEndian => b"II*\0".to_vec(),
+            ByteOrder::BigEndian => b"MM\0*".to_vec(),
+        };
+        order.put_u32(&mut out, 8);
+        for (ifd, raw) in &ifds {
+            let start = out.len();
+            let mut values_at = start + 2 + raw.len() * 12 + 4;
+            let mut values = Vec::new();
+            order.put_u16(&mut out, raw.len() as u16);
+            for (tag, field_type, count, bytes) in raw {
+                order.put_u16(&mut out, *tag);
+                order.put_u16(&mut out, *field_type);
+                order.put_u32(&mut out, *count);
+                if bytes.len() <= 4 {
+                    out.extend_from_slice(bytes);
+                    out.resize(out.len() + 4 - bytes.len(), 0);
+                } else {
+                    order.put_u32(&mut out, values_at as u32);
+                    values.extend_from_slice(bytes);
+                    // Values start on a word boundary.
+                    if bytes.len() % 2 == 1 {
+                        values.push(0);
+                    }
+                    values_at = start + 2 + raw.len() * 12 + 4 + values.len();
+                }
+            }
+            let next = match ifd {
+                Ifd::Primary => offset_of(Ifd::Thumbnail),
+                _ => 0,
+            };
+            order.put_u32(&mut out, next);
+            out.extend(values);
+        }
+        if let Some(thumbnail) = &self.thumbnail {
+            out.extend_from_slice(thumbnail);
+        }
+        out
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(Exif::chunk_type(), self.to_bytes())
+    }
+}
+
+impl TryFrom<&Chunk> for Exif {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Exif::chunk_type())?;
+        Exif::parse(chunk.data())
+    }
+}
+
+/// Renders every entry as a `directory  tag  name  value` table.
+pub fn format_table(exif: &Exif) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{:<7}  {:<6}  {:<22}  VALUE", "IFD", "TAG", "NAME");
+    for entry in &exif.entries {
+        let _ = writeln!(
+            out,
+            "{:<7}  {:#06x}  {:<22}  {}",
+            entry.ifd.to_string(),
+            entry.tag,
+            entry.name().unwrap_or("-"),
+            entry.value
+        );
+    }
+    if let Some(thumbnail) = &exif.thumbnail {
+        let _ = writeln!(out, "thumbnail: {} bytes", thumbnail.len());
+    }
+    out
+}
+
+impl Png {
+    /// Decodes the `eXIf` chunk, if there is one.
+    pub fn exif(&self) -> Result<Option<Exif>> {
+        self.chunk_by_type("eXIf").map(Exif::try_from).transpose()
+    }
+
+    /// Replaces the `eXIf` chunk, adding one before the image data if missing.
+    pub fn set_exif(&mut self, exif: &Exif) {
+        self.remove_chunks_where(|chunk| chunk.chunk_type() == Exif::chunk_type());
+        self.insert_before_image_data(exif.to_chunk());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ascii(s: &str) -> ExifValue {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
+        ExifValue::Ascii(bytes)
+    }
+
+    fn testing_exif(byte_order: ByteOrder) -> Exif {
+        Exif {
+            byte_order,
+            entries: vec![
+                ExifEntry {
+                    ifd: Ifd::Primary,
+                    tag: 0x010f,
+                    value: ascii("PNGMe Camera Co."),
+                },
+                ExifEntry {
+                    ifd: Ifd::Primary,
+                    tag: 0x0112,
+                    value: ExifValue::Short(vec![1]),
+                },
+                ExifEntry {
+                    ifd: Ifd::Exif,
+                    tag: 0x829a,
+                    value: ExifValue::Rational(vec![(1, 250)]),
+                },
+                ExifEntry {
+                    ifd: Ifd::Exif,
+                    tag: 0xa431,
+                    value: ascii("SN-123456"),
+                },
+                ExifEntry {
+                    ifd: Ifd::Interop,
+                    tag: 0x0001,
+                    value: ascii("R98"),
+                },
+                ExifEntry {
+                    ifd: Ifd::Gps,
+                    tag: 0x0001,
+                    value: ascii("N"),
+                },
+                ExifEntry {
+                    ifd: Ifd::Gps,
+                    tag: 0x0002,
+                    value: ExifValue::Rational(vec![(51, 1), (30, 1), (1234, 100)]),
+                },
+                ExifEntry {
+                    ifd: Ifd::Thumbnail,
+                    tag: 0x0103,
+                    value: ExifValue::Short(vec![6]),
+                },
+            ],
+            thumbnail: Some(b"\xff\xd8 fake jpeg \xff\xd9".to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for order in [ByteOrder::LittleEndian, ByteOrder::BigEndian] {
+            let exif = testing_exif(order);
+            let bytes = exif.to_bytes();
+            let parsed = Exif::parse(&bytes).unwrap();
+            assert_eq!(parsed, exif);
+            assert_eq!(parsed.to_bytes(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let exif = Exif {
+            byte_order: ByteOrder::BigEndian,
+            entries: vec![ExifEntry {
+                ifd: Ifd::Primary,
+                tag: 0x0112,
+                value: ExifValue::Short(vec![6]),
+            }],
+            thumbnail: None,
+        };
+        assert_eq!(
+            exif.to_bytes(),
+            [
+                b'M', b'M', 0, 42, 0, 0, 0, 8, // header
+                0, 1, // one entry
+                0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, // Orientation = 6
+                0, 0, 0, 0, // no next directory
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut exif = testing_exif(ByteOrder::LittleEndian);
+        let removed = exif.remove(TagSelector::from_str("GPS").unwrap());
+        assert_eq!(removed.len(), 2);
+        let removed = exif.remove(TagSelector::from_str("bodyserialnumber").unwrap());
+        assert_eq!(removed.len(), 1);
+        assert_eq!(
+            exif.remove(TagSelector::from_str("0x0112").unwrap()).len(),
+            1
+        );
+        exif.remove(TagSelector::from_str("thumbnail").unwrap());
+
+        let parsed = Exif::parse(&exif.to_bytes()).unwrap();
+        assert_eq!(parsed, exif);
+        assert_eq!(parsed.entries.len(), 3);
+        assert!(parsed.entries_in(Ifd::Gps).next().is_none());
+        assert!(parsed.thumbnail.is_none());
+        assert!(TagSelector::from_str("NotATag").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let exif = testing_exif(ByteOrder::LittleEndian);
+        assert_eq!(exif.entries[0].value.to_string(), "PNGMe Camera Co.");
+        assert_eq!(exif.entries[6].value.to_string(), "51/1, 30/1, 1234/100");
+        assert_eq!(exif.entries[6].name(), Some("GPSLatitude"));
+        let table = format_table(&exif);
+        assert_eq!(table.lines().count(), 1 + 8 + 1);
+        assert!(table.contains("BodySerialNumber"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Exif::parse(b"").is_err());
+        assert!(Exif::parse(b"XX*\0\x08\0\0\0").is_err());
+        // Header cut off inside the IFD0 offset.
+        assert!(matches!(
+            Exif::parse(b"II*\0\0\0"),
+            Err(PngError::InvalidChunkData { .. })
+        ));
+        // Directory offset past the end.
+        assert!(Exif::parse(b"II*\0\xff\0\0\0").is_err());
+        // IFD0 whose next pointer loops back to itself.
+        let looped = b"II*\0\x08\0\0\0\0\0\x08\0\0\0";
+        assert!(Exif::parse(looped).is_err());
+
+        let mut bytes = testing_exif(ByteOrder::BigEndian).to_bytes();
+        bytes.truncate(bytes.len() - 30);
+        assert!(Exif::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_png_exif() {
+        let mut png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), Vec::new()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        assert_eq!(png.exif().unwrap(), None);
+        let exif = testing_exif(ByteOrder::BigEndian);
+        png.set_exif(&exif);
+        png.set_exif(&exif);
+        assert_eq!(png.exif().unwrap(), Some(exif));
+        assert_eq!(png.chunks().len(), 4);
+        assert_eq!(png.chunks()[1].chunk_type().to_string(), "eXIf");
+    }
+}
@@ -0,0 +1,366 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_data::{check_type, expect_length, invalid, read_u32};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+
+/// How pixels are laid out, as stored in the IHDR colour type byte.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorType {
+    Grayscale = 0,
+    Rgb = 2,
+    Indexed = 3,
+    GrayscaleAlpha = 4,
+    Rgba = 6,
+}
+
+impl ColorType {
+    /// Bit depths the PNG spec allows for this colour type.
+    pub fn allowed_bit_depths(&self) -> &'static [u8] {
+        match self {
+            ColorType::Grayscale => &[1, 2, 4, 8, 16],
+            ColorType::Indexed => &[1, 2, 4, 8],
+            ColorType::Rgb | ColorType::GrayscaleAlpha | ColorType::Rgba => &[8, 16],
+        }
+    }
+
+    /// Samples per pixel. Indexed pixels are a single palette index.
+    pub fn channels(&self) -> u8 {
+        match self {
+            ColorType::Grayscale | ColorType::Indexed => 1,
+            ColorType::GrayscaleAlpha => 2,
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for ColorType {
+    type Error = PngError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(ColorType::Grayscale),
+            2 => Ok(ColorType::Rgb),
+            3 => Ok(ColorType::Indexed),
+            4 => Ok(ColorType::GrayscaleAlpha),
+            6 => Ok(ColorType::Rgba),
+            _ => Err(invalid(
+                Ihdr::chunk_type(),
+                format!("unknown colour type {}", value),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorType::Grayscale => "grayscale",
+            ColorType::Rgb => "RGB",
+            ColorType::Indexed => "indexed",
+            ColorType::GrayscaleAlpha => "grayscale + alpha",
+            ColorType::Rgba => "RGBA",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The seven Adam7 passes as `(x_start, y_start, x_step, y_step)`.
+pub const ADAM7_PASSES: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InterlaceMethod {
+    None = 0,
+    Adam7 = 1,
+}
+
+/// The decoded contents of the `IHDR` chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: ColorType,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: InterlaceMethod,
+}
+
+fn too_large() -> PngError {
+    PngError::CorruptImageData("image dimensions are too large".to_string())
+}
+
+impl Ihdr {
+    pub const LENGTH: usize = 13;
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("IHDR").unwrap()
+    }
+
+    /// Checks the field values against the rules in the PNG spec.
+    pub fn validate(&self) -> Result<()> {
+        if self.width == 0 || self.width > i32::MAX as u32 {
+            return Err(invalid(
+                Ihdr::chunk_type(),
+                format!("width {} is out of range", self.width),
+            ));
+        }
+        if self.height == 0 || self.height > i32::MAX as u32 {
+            return Err(invalid(
+                Ihdr::chunk_type(),
+                format!("height {} is out of range", self.height),
+            ));
+        }
+        if !self
+            .color_type
+            .allowed_bit_depths()
+            .contains(&self.bit_depth)
+        {
+            return Err(invalid(
+                Ihdr::chunk_type(),
+                format!(
+                    "bit depth {} is not allowed for {} images",
+                    self.bit_depth, self.color_type
+                ),
+            ));
+        }
+        if self.compression_method != 0 {
+            return Err(invalid(
+                Ihdr::chunk_type(),
+                format!("unknown compression method {}", self.compression_method),
+            ));
+        }
+        if self.filter_method != 0 {
+            return Err(invalid(
+                Ihdr::chunk_type(),
+                format!("unknown filter method {}", self.filter_method),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::with_capacity(Ihdr::LENGTH);
+        data.extend_from_slice(&self.width.to_be_bytes());
+        data.extend_from_slice(&self.height.to_be_bytes());
+        data.push(self.bit_depth);
+        data.push(self.color_type as u8);
+        data.push(self.compression_method);
+        data.push(self.filter_method);
+        data.push(self.interlace_method as u8);
+        Chunk::new(Ihdr::chunk_type(), data)
+    }
+
+    pub fn bits_per_pixel(&self) -> usize {
+        self.color_type.channels() as usize * self.bit_depth as usize
+    }
+
+    /// Bytes in one filtered scanline `width` pixels wide, including the
+    /// leading filter type byte.
+    pub fn scanline_len(&self, width: u32) -> Result<usize> {
+        (width as usize)
+            .checked_mul(self.bits_per_pixel())
+            .map(|bits| 1 + bits.div_ceil(8))
+            .ok_or_else(too_large)
+    }
+
+    /// Width and height of each reduced image stored in the image data: the
+    /// whole image, or the seven Adam7 passes. Empty passes are included.
+    pub fn pass_dimensions(&self) -> Vec<(u32, u32)> {
+        match self.interlace_method {
+            InterlaceMethod::None => vec![(self.width, self.height)],
+            InterlaceMethod::Adam7 => ADAM7_PASSES
+                .iter()
+                .map(|&(x0, y0, dx, dy)| {
+                    (
+                        self.width.saturating_sub(x0).div_ceil(dx),
+                        self.height.saturating_sub(y0).div_ceil(dy),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Length of the inflated image data: every scanline of every non-empty
+    /// pass, filter type bytes included.
+    /// Fails with `CorruptImageData` when the size does not fit in a `usize`.
+    pub fn image_data_len(&self) -> Result<usize> {
+        self.pass_dimensions()
+            .into_iter()
+            .filter(|&(width, height)| width > 0 && height > 0)
+            .try_fold(0usize, |total, (width, height)| {
+                self.scanline_len(width)?
+                    .checked_mul(height as usize)
+                    .and_then(|len| total.checked_add(len))
+                    .ok_or_else(too_large)
+            })
+    }
+}
+
+impl TryFrom<&Chunk> for Ihdr {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Ihdr::chunk_type())?;
+        expect_length(chunk, Ihdr::LENGTH)?;
+        let data = chunk.data();
+
+        let interlace_method = match data[12] {
+            0 => InterlaceMethod::None,
+            1 => InterlaceMethod::Adam7,
+            other => {
+                return Err(invalid(
+                    Ihdr::chunk_type(),
+                    format!("unknown interlace method {}", other),
+                ))
+            }
+        };
+        let ihdr = Ihdr {
+            width: read_u32(data, 0),
+            height: read_u32(data, 4),
+            bit_depth: data[8],
+            color_type: ColorType::try_from(data[9])?,
+            compression_method: data[10],
+            filter_method: data[11],
+            interlace_method,
+        };
+        ihdr.validate()?;
+        Ok(ihdr)
+    }
+}
+
+impl fmt::Display for Ihdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "width:       {}", self.width)?;
+        writeln!(f, "height:      {}", self.height)?;
+        writeln!(f, "bit depth:   {}", self.bit_depth)?;
+        writeln!(
+            f,
+            "colour type: {} ({})",
+            self.color_type, self.color_type as u8
+        )?;
+        writeln!(f, "compression: {}", self.compression_method)?;
+        writeln!(f, "filter:      {}", self.filter_method)?;
+        write!(
+            f,
+            "interlace:   {}",
+            match self.interlace_method {
+                InterlaceMethod::None => "none",
+                InterlaceMethod::Adam7 => "Adam7",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_ihdr() -> Ihdr {
+        Ihdr {
+            width: 640,
+            height: 480,
+            bit_depth: 8,
+            color_type: ColorType::Rgba,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+    }
+
+    #[test]
+    fn test_ihdr_round_trip() {
+        let ihdr = testing_ihdr();
+        let chunk = ihdr.to_chunk();
+        assert_eq!(chunk.length(), 13);
+        assert_eq!(Ihdr::try_from(&chunk).unwrap(), ihdr);
+    }
+
+    #[test]
+    fn test_ihdr_rejects_bad_bit_depth() {
+        let ihdr = Ihdr {
+            bit_depth: 4,
+            ..testing_ihdr()
+        };
+        assert!(Ihdr::try_from(&ihdr.to_chunk()).is_err());
+
+        let ihdr = Ihdr {
+            color_type: ColorType::Indexed,
+            bit_depth: 16,
+            ..testing_ihdr()
+        };
+        assert!(ihdr.validate().is_err());
+    }
+
+    #[test]
+    fn test_ihdr_rejects_zero_width() {
+        let ihdr = Ihdr {
+            width: 0,
+            ..testing_ihdr()
+        };
+        assert!(ihdr.validate().is_err());
+    }
+
+    #[test]
+    fn test_ihdr_rejects_unknown_color_type() {
+        let mut data = testing_ihdr().to_chunk().data().to_vec();
+        data[9] = 5;
+        let chunk = Chunk::new(Ihdr::chunk_type(), data);
+        assert!(matches!(
+            Ihdr::try_from(&chunk),
+            Err(PngError::InvalidChunkData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_image_data_len() {
+        let ihdr = testing_ihdr();
+        assert_eq!(ihdr.scanline_len(ihdr.width).unwrap(), 1 + 640 * 4);
+        assert_eq!(ihdr.image_data_len().unwrap(), 480 * (1 + 640 * 4));
+
+        let ihdr = Ihdr {
+            width: 3,
+            height: 2,
+            bit_depth: 1,
+            color_type: ColorType::Grayscale,
+            interlace_method: InterlaceMethod::Adam7,
+            ..testing_ihdr()
+        };
+        assert_eq!(ihdr.scanline_len(3).unwrap(), 2);
+        assert_eq!(
+            ihdr.pass_dimensions(),
+            [(1, 1), (0, 1), (1, 0), (1, 1), (2, 0), (1, 1), (3, 1)]
+        );
+        // Passes 1, 4, 6 and 7 hold one scanline each.
+        assert_eq!(ihdr.image_data_len().unwrap(), 4 * 2);
+
+        let ihdr = Ihdr {
+            width: i32::MAX as u32,
+            height: i32::MAX as u32,
+            bit_depth: 16,
+            ..testing_ihdr()
+        };
+        assert!(ihdr.validate().is_ok());
+        assert!(matches!(
+            ihdr.image_data_len(),
+            Err(PngError::CorruptImageData(_))
+        ));
+    }
+
+    #[test]
+    fn test_ihdr_rejects_wrong_length() {
+        let chunk = Chunk::new(Ihdr::chunk_type(), vec![0; 12]);
+        assert!(Ihdr::try_from(&chunk).is_err());
+    }
+}
@@ -0,0 +1,582 @@
+use std::fmt;
+
+use crate::error::{PngError, Result};
+use crate::ihdr::{ColorType, Ihdr, InterlaceMethod, ADAM7_PASSES};
+use crate::palette::{Palette, Transparency};
+use crate::png::Png;
+
+fn corrupt(reason: String) -> PngError {
+    PngError::CorruptImageData(reason)
+}
+
+/// The most bytes pngme holds for one image, as inflated scanlines or as
+/// decoded pixels, so a small file cannot declare a multi-gigabyte image.
+pub const MAX_IMAGE_LEN: usize = 1 << 30;
+
+/// Fails with `CorruptImageData` when `len` bytes of `what` exceed `MAX_IMAGE_LEN`.
+pub(crate) fn check_image_len(len: usize, what: &str) -> Result<usize> {
+    if len > MAX_IMAGE_LEN {
+        return Err(corrupt(format!(
+            "{} would take {} bytes, more than the limit of {}",
+            what, len, MAX_IMAGE_LEN
+        )));
+    }
+    Ok(len)
+}
+
+/// How a scanline was filtered, from the byte that starts it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FilterType {
+    None = 0,
+    Sub = 1,
+    Up = 2,
+    Average = 3,
+    Paeth = 4,
+}
+
+impl TryFrom<u8> for FilterType {
+    type Error = PngError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(FilterType::None),
+            1 => Ok(FilterType::Sub),
+            2 => Ok(FilterType::Up),
+            3 => Ok(FilterType::Average),
+            4 => Ok(FilterType::Paeth),
+            _ => Err(corrupt(format!("unknown filter type {}", value))),
+        }
+    }
+}
+
+/// Whichever of left, above and upper left is closest to `a + b - c`.
+fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reverses `filter` on `row` in place. `previous` is the unfiltered row
+/// above in the same pass, all zeros for the first row, and `bpp` the bytes
+/// per complete pixel, rounded up to at least one.
+pub fn unfilter(filter: FilterType, row: &mut [u8], previous: &[u8], bpp: usize) {
+    match filter {
+        FilterType::None => {}
+        FilterType::Sub => {
+            for i in bpp..row.len() {
+                row[i] = row[i].wrapping_add(row[i - bpp]);
+            }
+        }
+        FilterType::Up => {
+            for (byte, &above) in row.iter_mut().zip(previous) {
+                *byte = byte.wrapping_add(above);
+            }
+        }
+        FilterType::Average => {
+            for i in 0..row.len() {
+                let left = if i >= bpp { row[i - bpp] } else { 0 };
+                let average = (left as u16 + previous[i] as u16) / 2;
+                row[i] = row[i].wrapping_add(average as u8);
+            }
+        }
+        FilterType::Paeth => {
+            for i in 0..row.len() {
+                let (left, upper_left) = if i >= bpp {
+                    (row[i - bpp], previous[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                row[i] = row[i].wrapping_add(paeth_predictor(left, previous[i], upper_left));
+            }
+        }
+    }
+}
+
+/// Sample `x` of a row packing one sample of `bits` bits per pixel, most
+/// significant bits first.
+fn packed_sample(row: &[u8], x: usize, bits: usize) -> u8 {
+    let bit = x * bits;
+    let mask = ((1u16 << bits) - 1) as u8;
+    (row[bit / 8] >> (8 - bits - bit % 8)) & mask
+}
+
+/// A fully decoded image, with the pixels' samples interleaved in row-major
+/// order.
+///
+/// Indexed images are expanded to RGB, or RGBA when `tRNS` gives alpha
+/// values. Grayscale below 8 bits is scaled up to 8 bits, and 16-bit samples
+/// are kept big-endian. `tRNS` colour keys of grayscale and RGB images are not
+/// applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Never `Indexed`.
+    pub color_type: ColorType,
+    /// 8 or 16.
+    pub bit_depth: u8,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Unfilters and de-interlaces `image_data`, the inflated `IDAT` contents,
+    /// into pixels. Indexed images need their palette.
+    pub fn decode(
+        ihdr: &Ihdr,
+        image_data: &[u8],
+        palette: Option<&Palette>,
+        transparency: Option<&Transparency>,
+    ) -> Result<Image> {
+        let palette = match ihdr.color_type {
+            ColorType::Indexed => {
+                Some(palette.ok_or_else(|| PngError::ChunkNotFound("PLTE".to_string()))?)
+            }
+            _ => None,
+        };
+        let alpha = transparency.filter(|t| matches!(t, Transparency::Indexed(_)));
+        let color_type = match ihdr.color_type {
+            ColorType::Indexed if alpha.is_some() => ColorType::Rgba,
+            ColorType::Indexed => ColorType::Rgb,
+            color_type => color_type,
+        };
+        let bit_depth = if ihdr.bit_depth == 16 { 16 } else { 8 };
+        let mut image = Image {
+            width: ihdr.width,
+            height: ihdr.height,
+            color_type,
+            bit_depth,
+            data: Vec::new(),
+        };
+        // Checked before allocating, so the header alone cannot size the buffer.
+        let out_bpp = image.bytes_per_pixel();
+        let out_len = (ihdr.width as usize)
+            .checked_mul(ihdr.height as usize)
+            .and_then(|pixels| pixels.checked_mul(out_bpp))
+            .ok_or_else(|| corrupt("image dimensions are too large".to_string()))?;
+        let out_len = check_image_len(out_len, "decoded pixels")?;
+        let expected_len = ihdr.image_data_len()?;
+        if image_data.len() != expected_len {
+            return Err(corrupt(format!(
+                "expected {} bytes of scanlines, found {}",
+                expected_len,
+                image_data.len()
+            )));
+        }
+        image.data = vec![0; out_len];
+
+        let bits = ihdr.bit_depth as usize;
+        let in_bpp = ihdr.bits_per_pixel().div_ceil(8);
+        let passes = match ihdr.interlace_method {
+            InterlaceMethod::None => vec![(0, 0, 1, 1)],
+            InterlaceMethod::Adam7 => ADAM7_PASSES.to_vec(),
+        };
+        let mut rest = image_data;
+        for ((x0, y0, dx, dy), (width, height)) in passes.into_iter().zip(ihdr.pass_dimensions()) {
+            if width == 0 || height == 0 {
+                continue;
+            }
+            let len = ihdr.scanline_len(width)?;
+            let mut previous = vec![0; len - 1];
+            for y in 0..height {
+                // The total length was checked above.
+                let (line, tail) = rest.split_at(len);
+                rest = tail;
+                let mut row = line[1..].to_vec();
+                unfilter(FilterType::try_from(line[0])?, &mut row, &previous, in_bpp);
+
+                let out_y = (y0 + y * dy) as usize;
+                for x in 0..width as usize {
+                    let out_x = x0 as usize + x * dx as usize;
+                    let start = (out_y * ihdr.width as usize + out_x) * out_bpp;
+                    let out = &mut image.data[start..start + out_bpp];
+                    match (ihdr.color_type, palette) {
+                        (ColorType::Indexed, Some(palette)) => {
+                            let index = packed_sample(&row, x, bits) as usize;
+                            let entry = palette.entries.get(index).ok_or_else(|| {
+                                corrupt(format!(
+                                    "palette index {} is out of range for {} entries",
+                                    index,
+                                    palette.entries.len()
+                                ))
+                            })?;
+                            out[..3].copy_from_slice(&[entry.red, entry.green, entry.blue]);
+                            if let Some(alpha) = alpha {
+                                out[3] = alpha.alpha(index);
+                            }
+                        }
+                        (ColorType::Grayscale, _) if bits < 8 => {
+                            let max = (1u16 << bits) - 1;
+                            let value = packed_sample(&row, x, bits) as u16;
+                            out[0] = (value * 255 / max) as u8;
+                        }
+                        _ => out.copy_from_slice(&row[x * out_bpp..(x + 1) * out_bpp]),
+                    }
+                }
+                previous = row;
+            }
+        }
+        Ok(image)
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.color_type.channels() as usize * self.bit_depth as usize / 8
+    }
+
+    /// The samples of the pixel at column `x` of row `y`.
+    pub fn pixel(&self, x: u32, y: u32) -> &[u8] {
+        let bpp = self.bytes_per_pixel();
+        let start = (y as usize * self.width as usize + x as usize) * bpp;
+        &self.data[start..start + bpp]
+    }
+
+    /// How `other` differs from this image, or `None` if every pixel matches.
+    pub fn compare(&self, other: &Image) -> Option<ImageDifference> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Some(ImageDifference::Dimensions {
+                first: (self.width, self.height),
+                second: (other.width, other.height),
+            });
+        }
+        if (self.color_type, self.bit_depth) != (other.color_type, other.bit_depth) {
+            return Some(ImageDifference::Format {
+                first: (self.color_type, self.bit_depth),
+                second: (other.color_type, other.bit_depth),
+            });
+        }
+        let bpp = self.bytes_per_pixel();
+        let mut differing = self
+            .data
+            .chunks_exact(bpp)
+            .zip(other.data.chunks_exact(bpp))
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, _)| i);
+        let first = differing.next()?;
+        Some(ImageDifference::Pixels {
+            first: (first as u32 % self.width, first as u32 / self.width),
+            count: 1 + differing.count(),
+        })
+    }
+}
+
+/// The first way two decoded images were found to differ.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageDifference {
+    Dimensions {
+        first: (u32, u32),
+        second: (u32, u32),
+    },
+    /// Colour type and bit depth.
+    Format {
+        first: (ColorType, u8),
+        second: (ColorType, u8),
+    },
+    /// `count` pixels differ, the first in reading order at `first`.
+    Pixels { first: (u32, u32), count: usize },
+}
+
+impl fmt::Display for ImageDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageDifference::Dimensions { first, second } => write!(
+                f,
+                "dimensions differ: {}x{} and {}x{}",
+                first.0, first.1, second.0, second.1
+            ),
+            ImageDifference::Format { first, second } => write!(
+                f,
+                "pixel formats differ: {} {}-bit and {} {}-bit",
+                first.0, first.1, second.0, second.1
+            ),
+            ImageDifference::Pixels { first, count: 1 } => {
+                write!(f, "pixel ({}, {}) differs", first.0, first.1)
+            }
+            ImageDifference::Pixels { first, count } => write!(
+                f,
+                "{} pixels differ, the first at ({}, {})",
+                count, first.0, first.1
+            ),
+        }
+    }
+}
+
+impl Png {
+    /// Inflates, unfilters and de-interlaces the image data into pixels.
+    pub fn decode_image(&self) -> Result<Image> {
+        let header = self.header_info()?;
+        let image_data = self.image_data()?;
+        if header.color_type != ColorType::Indexed {
+            return Image::decode(&header, &image_data, None, None);
+        }
+        let palette = self.palette()?;
+        let transparency = self.transparency()?;
+        Image::decode(
+            &header,
+            &image_data,
+            palette.as_ref(),
+            transparency.as_ref(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::palette::PaletteEntry;
+    use crate::zlib;
+    use std::str::FromStr;
+
+    /// Applies `filter` the way an encoder would, for round-trip tests.
+    fn filter(filter: FilterType, row: &[u8], previous: &[u8], bpp: usize) -> Vec<u8> {
+        (0..row.len())
+            .map(|i| {
+                let left = if i >= bpp { row[i - bpp] } else { 0 };
+                let upper_left = if i >= bpp { previous[i - bpp] } else { 0 };
+                let predictor = match filter {
+                    FilterType::None => 0,
+                    FilterType::Sub => left,
+                    FilterType::Up => previous[i],
+                    FilterType::Average => ((left as u16 + previous[i] as u16) / 2) as u8,
+                    FilterType::Paeth => paeth_predictor(left, previous[i], upper_left),
+                };
+                row[i].wrapping_sub(predictor)
+            })
+            .collect()
+    }
+
+    fn ihdr(width: u32, height: u32, bit_depth: u8, color_type: ColorType) -> Ihdr {
+        Ihdr {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+    }
+
+    #[test]
+    fn test_unfilter_round_trip() {
+        let previous: Vec<u8> = (0..12u32).map(|i| (i * 37) as u8).collect();
+        let row: Vec<u8> = (0..12).map(|i| 200 - i * 13).collect();
+        for filter_type in [
+            FilterType::None,
+            FilterType::Sub,
+            FilterType::Up,
+            FilterType::Average,
+            FilterType::Paeth,
+        ] {
+            for bpp in [1, 3, 6] {
+                let mut filtered = filter(filter_type, &row, &previous, bpp);
+                unfilter(filter_type, &mut filtered, &previous, bpp);
+                assert_eq!(
+                    filtered, row,
+                    "{:?} with {} bytes per pixel",
+                    filter_type, bpp
+                );
+            }
+        }
+        assert!(FilterType::try_from(5).is_err());
+    }
+
+    #[test]
+    fn test_paeth_predictor() {
+        assert_eq!(paeth_predictor(10, 20, 10), 20);
+        assert_eq!(paeth_predictor(20, 10, 10), 20);
+        assert_eq!(paeth_predictor(10, 10, 20), 10);
+        assert_eq!(paeth_predictor(0, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_decode_rgb_16() {
+        let ihdr = ihdr(2, 2, 16, ColorType::Rgb);
+        let rows = [
+            [0x12, 0x34, 0, 0, 0xff, 0xff, 1, 2, 3, 4, 5, 6],
+            [9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0xab, 0xcd],
+        ];
+        let mut data = vec![FilterType::Sub as u8];
+        data.extend(filter(FilterType::Sub, &rows[0], &[0; 12], 6));
+        data.push(FilterType::Paeth as u8);
+        data.extend(filter(FilterType::Paeth, &rows[1], &rows[0], 6));
+
+        let image = Image::decode(&ihdr, &data, None, None).unwrap();
+        assert_eq!((image.color_type, image.bit_depth), (ColorType::Rgb, 16));
+        assert_eq!(image.data, rows.concat());
+        assert_eq!(image.pixel(1, 1), [3, 2, 1, 0, 0xab, 0xcd]);
+
+        assert!(Image::decode(&ihdr, &data[..data.len() - 1], None, None).is_err());
+        data.push(0);
+        assert!(Image::decode(&ihdr, &data, None, None).is_err());
+        data.pop();
+        data[0] = 9;
+        assert!(matches!(
+            Image::decode(&ihdr, &data, None, None),
+            Err(PngError::CorruptImageData(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_low_bit_depths() {
+        // 2-bit grayscale: 0, 1, 2, 3, 0 packed into two bytes.
+        let image = Image::decode(
+            &ihdr(5, 1, 2, ColorType::Grayscale),
+            &[0, 0b0001_1011, 0b0000_0000],
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(image.data, [0, 85, 170, 255, 0]);
+
+        // 1-bit indexed with a two colour palette and a transparent entry 0.
+        let palette = Palette {
+            entries: vec![
+                PaletteEntry {
+                    red: 1,
+                    green: 2,
+                    blue: 3,
+                },
+                PaletteEntry {
+                    red: 4,
+                    green: 5,
+                    blue: 6,
+                },
+            ],
+        };
+        let ihdr = ihdr(3, 1, 1, ColorType::Indexed);
+        let data = [0, 0b1010_0000];
+        let image = Image::decode(&ihdr, &data, Some(&palette), None).unwrap();
+        assert_eq!(image.color_type, ColorType::Rgb);
+        assert_eq!(image.data, [4, 5, 6, 1, 2, 3, 4, 5, 6]);
+
+        let transparency = Transparency::Indexed(vec![0]);
+        let image = Image::decode(&ihdr, &data, Some(&palette), Some(&transparency)).unwrap();
+        assert_eq!(image.color_type, ColorType::Rgba);
+        assert_eq!(image.pixel(1, 0), [1, 2, 3, 0]);
+        assert_eq!(image.pixel(2, 0), [4, 5, 6, 255]);
+
+        let short_palette = Palette {
+            entries: palette.entries[..1].to_vec(),
+        };
+        assert!(Image::decode(&ihdr, &data, Some(&short_palette), None).is_err());
+        assert!(matches!(
+            Image::decode(&ihdr, &data, None, None),
+            Err(PngError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_adam7() {
+        // Each pixel of a 3x2 8-bit grayscale image holds 10 * y + x.
+        let ihdr = Ihdr {
+            interlace_method: InterlaceMethod::Adam7,
+            ..ihdr(3, 2, 8, ColorType::Grayscale)
+        };
+        let data = [
+            0, 0, // pass 1: (0, 0)
+            0, 2, // pass 4: (2, 0)
+            0, 1, // pass 6: (1, 0)
+            0, 10, 11, 12, // pass 7: row 1
+        ];
+        let image = Image::decode(&ihdr, &data, None, None).unwrap();
+        assert_eq!(image.data, [0, 1, 2, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_header() {
+        for (width, height) in [(100_000, 100_000), (i32::MAX as u32, i32::MAX as u32)] {
+            let ihdr = ihdr(width, height, 16, ColorType::Rgba);
+            assert!(matches!(
+                Image::decode(&ihdr, &[0; 9], None, None),
+                Err(PngError::CorruptImageData(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_caps_image_size() {
+        // 65536x65536 at 1 bit per index needs 512 MiB of scanlines but 12 GiB of RGB.
+        let mut indexed = ihdr(65_536, 65_536, 1, ColorType::Indexed);
+        let palette = Palette {
+            entries: vec![
+                PaletteEntry {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                };
+                2
+            ],
+        };
+        let err = Image::decode(&indexed, &[0; 9], Some(&palette), None).unwrap_err();
+        assert!(err.to_string().contains("decoded pixels"), "{}", err);
+
+        // The scanline size is checked before a tiny stream is inflated.
+        indexed.bit_depth = 8;
+        indexed.color_type = ColorType::Grayscale;
+        let png = Png::from_chunks(vec![
+            indexed.to_chunk(),
+            Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                zlib::compress(&[0; 64]),
+            ),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let err = png.image_data().unwrap_err();
+        assert!(err.to_string().contains("scanlines would take"), "{}", err);
+    }
+
+    #[test]
+    fn test_compare() {
+        let ihdr = ihdr(2, 2, 8, ColorType::Grayscale);
+        let image = Image::decode(&ihdr, &[0, 1, 2, 0, 3, 4], None, None).unwrap();
+        assert_eq!(image.compare(&image.clone()), None);
+
+        let mut other = image.clone();
+        other.data[1] = 9;
+        other.data[3] = 9;
+        let difference = image.compare(&other).unwrap();
+        assert_eq!(
+            difference,
+            ImageDifference::Pixels {
+                first: (1, 0),
+                count: 2
+            }
+        );
+        assert_eq!(
+            difference.to_string(),
+            "2 pixels differ, the first at (1, 0)"
+        );
+
+        other.width = 4;
+        other.height = 1;
+        assert!(matches!(
+            image.compare(&other),
+            Some(ImageDifference::Dimensions { .. })
+        ));
+    }
+
+    #[test]
+    fn test_png_decode_image() {
+        let ihdr = ihdr(2, 1, 8, ColorType::Rgba);
+        let png = Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                zlib::compress(&[FilterType::None as u8, 1, 2, 3, 4, 5, 6, 7, 8]),
+            ),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let image = png.decode_image().unwrap();
+        assert_eq!(image.data, [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
@@ -1,107 +1,350 @@
 use std::fs::File;
-use std::io::{self, Bytes, Read, Write};
+use std::io::{self, Write};
+use std::path::Path;
 
 use args::Config;
 use chunk::Chunk;
-use commands::{Commands, OperationResult};
-use png::Png;
+use color::ColorInfo;
+use commands::{
+    ColorCommand, Commands, ExifCommand, OperationResult, OutputArgs, TextCommand, XmpCommand,
+};
+use error::PngError;
+use exif::Exif;
+use list::ChunkSummary;
+use png::{CrcPolicy, ParseOptions, Png};
+use text::{CompressedTextChunk, InternationalTextChunk, TextChunk, TextEntry};
+use time::Time;
+use validate::Diagnostic;
+use xmp::{Xmp, XMP_KEYWORD};
+pub mod ancillary;
 pub mod args;
 pub mod chunk;
+mod chunk_data;
+pub mod chunk_ref;
 pub mod chunk_type;
+pub mod color;
 pub mod commands;
+pub mod error;
+pub mod exif;
+pub mod ihdr;
+pub mod image;
+pub mod list;
+pub mod mmap;
+pub mod palette;
 pub mod png;
+pub mod png_ref;
+pub mod reader;
+pub mod text;
+pub mod time;
+pub mod validate;
+pub mod writer;
+pub mod xmp;
+pub mod zlib;
 
-pub fn read_file(file_name: &str) -> Result<Vec<u8>, std::io::Error> {
-    let mut file = File::open(&file_name)?;
-    let metadata = std::fs::metadata(&file_name)?;
-    let mut buffer: Vec<u8> = vec![0; metadata.len() as usize];
-    file.read(&mut buffer).expect("buffer overeflow");
-    Ok(buffer)
+pub fn read_file(path: impl AsRef<Path>) -> Result<Vec<u8>, std::io::Error> {
+    std::fs::read(path)
 }
 
-pub fn write_file(name: &String, byte: Vec<u8>) -> std::io::Result<()> {
-    let nw_file_name = format!("{}.png", name);
-    let mut file = File::create(nw_file_name)?;
+pub fn write_file(path: impl AsRef<Path>, byte: Vec<u8>) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
     file.write_all(byte.as_slice())
 }
 
-pub fn check_string(s: &str) -> Option<&str> {
-    if !s.is_empty() {
-        Some(s)
-    } else {
-        None
+/// Replaces `path` with `bytes` by writing a temporary file next to it and
+/// renaming it over the original, so readers never see a half written file.
+pub fn write_file_atomic(path: impl AsRef<Path>, bytes: &[u8]) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".pngme-{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+    match result.and_then(|_| std::fs::rename(&temp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+/// Writes `png` to wherever `destination` points. `input` is the file that
+/// `--in-place` replaces.
+fn write_output(png: &Png, input: &Path, destination: &OutputArgs) -> Result<(), PngError> {
+    if destination.in_place() {
+        write_file_atomic(input, &png.as_bytes())?;
+    } else if let Some(output) = destination.output() {
+        write_file(output, png.as_bytes())?;
+    } else if destination.stdout() {
+        png.write_to(&mut io::stdout().lock())?;
     }
+    Ok(())
 }
 
-pub fn operation(config: &Config) -> Result<OperationResult, String> {
-        // println!("{:?}", &self.command);
-        match &config.command {
-            Commands::Encode(encode_cmd) => {
-                let mut png = match Png::try_from(encode_cmd.data().as_slice()) {
-                    Ok(png) => png,
-                    Err(_) => return Err("Error".to_string()), // fix later on
-                };
-                let new_data = Chunk::new(
-                    encode_cmd.chunk_type().clone(),
-                    encode_cmd.message().as_bytes().to_vec(),
-                );
-                png.append_chunk(new_data);
-
-                if !encode_cmd.output().is_empty() {
-                    let _ = write_file(encode_cmd.output(), png.as_bytes());
+/// Unwraps `result`, or records its error in `diagnostics` and falls back to
+/// `fallback` so one bad chunk does not hide the rest of a view.
+fn or_diagnostic<T>(
+    result: Result<T, PngError>,
+    fallback: T,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> T {
+    result.unwrap_or_else(|error| {
+        diagnostics.push(Diagnostic::error(None, error.to_string()));
+        fallback
+    })
+}
+
+fn missing_xmp() -> PngError {
+    PngError::ChunkNotFound(format!("iTXt {:?}", XMP_KEYWORD))
+}
+
+pub fn operation(config: &Config) -> Result<OperationResult, PngError> {
+    match &config.command {
+        Commands::Encode(encode_cmd) => {
+            if !encode_cmd.chunk_type().is_reserved_bit_valid() && !encode_cmd.force() {
+                return Err(PngError::ReservedBitSet(*encode_cmd.chunk_type()));
+            }
+            let mut png = Png::try_from(read_file(encode_cmd.file())?.as_slice())?;
+            let new_data = Chunk::new(
+                *encode_cmd.chunk_type(),
+                encode_cmd.message().as_bytes().to_vec(),
+            );
+            png.append_chunk(new_data);
+            if encode_cmd.touch() {
+                png.set_last_modified(Time::now())?;
+            }
+
+            write_output(&png, encode_cmd.file(), encode_cmd.destination())?;
+            Ok(OperationResult::EncodedPng(png))
+        }
+        Commands::Decode(decode_cmd) => {
+            let mapped = Png::open_mmap(decode_cmd.file())?;
+            let png = mapped.png_ref()?;
+            png.verify_crcs()?;
+
+            let chunk_type = decode_cmd.chunk_type().to_string();
+            let message = match png.chunk_by_type(&chunk_type) {
+                // Compressed text would otherwise come back as raw deflate bytes.
+                Some(chunk) if TextEntry::is_text_type(chunk.chunk_type()) => {
+                    TextEntry::try_from(&chunk.to_owned())?.text().to_string()
                 }
+                Some(chunk) => chunk.data_as_str()?.to_string(),
+                None => return Err(PngError::ChunkNotFound(chunk_type)),
+            };
+
+            Ok(OperationResult::DecodedMessage(message))
+        }
+        Commands::Remove(remove_cmd) => {
+            let mut png = Png::try_from(read_file(remove_cmd.file())?.as_slice())?;
+
+            let chunk_type = remove_cmd.chunk_type().to_string();
+            let chunks = if remove_cmd.all() {
+                png.remove_all_chunks(&chunk_type)?
+            } else {
+                vec![png.remove_chunk(&chunk_type)?]
+            };
+            if remove_cmd.touch() {
+                png.set_last_modified(Time::now())?;
+            }
+
+            write_output(&png, remove_cmd.file(), remove_cmd.destination())?;
+
+            Ok(OperationResult::RemovedChunks(chunks))
+        }
+        Commands::Print(print_cmd) => {
+            let mapped = Png::open_mmap(print_cmd.file())?;
+            let png = mapped.png_ref()?;
+            png.verify_crcs()?;
 
-                Ok(OperationResult::EncodedPng(png))
+            let chunk_type = print_cmd.chunk_type().to_string();
+            let chunk = match png.chunk_by_type(&chunk_type) {
+                Some(chunk) => chunk,
+                None => return Err(PngError::ChunkNotFound(chunk_type)),
+            };
+            println!("{}", chunk.chunk_type());
+
+            let mut stdout = io::stdout();
+            let result: Result<(), io::Error> = writeln!(
+                stdout,
+                "Executing print command\n and the chunk is === {}",
+                chunk.chunk_type()
+            ); // Returns Result
+
+            Ok(OperationResult::PrintedInfo(result))
+        }
+        Commands::Info(info_cmd) => {
+            let png = Png::try_from(read_file(info_cmd.file())?.as_slice())?;
+            if !info_cmd.all() {
+                return Ok(OperationResult::HeaderInfo(png.header_info()?));
             }
-            Commands::Decode(decode_cmd) => {
-                let png = match Png::try_from(decode_cmd.data().as_slice()) {
-                    Ok(png) => png,
-                    Err(e) => return Err(e.to_string()),
-                };
-
-                let message = match png.chunk_by_type(&decode_cmd.chunk_type().to_string()) {
-                    Some(chunk) => match chunk.data_as_string() {
-                        Ok(msg) => msg,
-                        Err(e) => return Err(e.to_string()),
-                    },
-                    None => "Chunk not found".to_string(),
-                };
-
-                Ok(OperationResult::DecodedMessage(message))
+            let header = png.header_info()?;
+            let mut diagnostics = Vec::new();
+            Ok(OperationResult::FullInfo {
+                header,
+                palette: or_diagnostic(png.palette(), None, &mut diagnostics),
+                color: Box::new(or_diagnostic(
+                    png.color_info(),
+                    ColorInfo::default(),
+                    &mut diagnostics,
+                )),
+                ancillary: Box::new(png.ancillary_info()),
+                last_modified: or_diagnostic(png.last_modified(), None, &mut diagnostics),
+                diagnostics,
+            })
+        }
+        Commands::Validate(validate_cmd) => {
+            let options = ParseOptions::default().crc_policy(CrcPolicy::Warn);
+            let png = Png::parse(&read_file(validate_cmd.file())?, &options)?;
+            Ok(OperationResult::Validated(png.validate()))
+        }
+        Commands::Compare(compare_cmd) => {
+            let first = Png::try_from(read_file(compare_cmd.first())?.as_slice())?;
+            let second = Png::try_from(read_file(compare_cmd.second())?.as_slice())?;
+            let difference = first.decode_image()?.compare(&second.decode_image()?);
+            Ok(OperationResult::Compared(difference))
+        }
+        Commands::List(list_cmd) => {
+            // Bad CRCs are reported per chunk rather than failing the listing.
+            let mapped = Png::open_mmap(list_cmd.file())?;
+            Ok(OperationResult::ChunkList {
+                chunks: ChunkSummary::from_png_ref(&mapped.png_ref()?),
+                json: list_cmd.json(),
+            })
+        }
+        Commands::Palette(palette_cmd) => {
+            let png = Png::try_from(read_file(palette_cmd.file())?.as_slice())?;
+            let palette = png
+                .palette()?
+                .ok_or_else(|| PngError::ChunkNotFound("PLTE".to_string()))?;
+            Ok(OperationResult::PaletteInfo {
+                palette,
+                transparency: png.transparency()?,
+                json: palette_cmd.json(),
+            })
+        }
+        Commands::Color(ColorCommand::Show(show_cmd)) => {
+            let png = Png::try_from(read_file(show_cmd.file())?.as_slice())?;
+            Ok(OperationResult::ColorInfo(png.color_info()?))
+        }
+        Commands::Color(ColorCommand::Strip(strip_cmd)) => {
+            let mut png = Png::try_from(read_file(strip_cmd.file())?.as_slice())?;
+            let removed = png.strip_color_info();
+            write_output(&png, strip_cmd.file(), strip_cmd.destination())?;
+            Ok(OperationResult::RemovedChunks(removed))
+        }
+        Commands::Color(ColorCommand::ExtractIcc(extract_cmd)) => {
+            let png = Png::try_from(read_file(extract_cmd.file())?.as_slice())?;
+            let profile = png
+                .color_info()?
+                .icc_profile
+                .ok_or_else(|| PngError::ChunkNotFound("iCCP".to_string()))?;
+            let path = extract_cmd.output();
+            write_file(&path, profile.profile.clone())?;
+            Ok(OperationResult::ExtractedProfile { path, profile })
+        }
+        Commands::Text(TextCommand::Get(get_cmd)) => {
+            let png = Png::try_from(read_file(get_cmd.file())?.as_slice())?;
+            match png.text(get_cmd.keyword())? {
+                Some(entry) => Ok(OperationResult::DecodedMessage(entry.text().to_string())),
+                None => Err(PngError::ChunkNotFound(format!(
+                    "tEXt {:?}",
+                    get_cmd.keyword()
+                ))),
             }
-            Commands::Remove(remove_cmd) => {
-                let mut png = match Png::try_from(remove_cmd.data().as_slice()) {
-                    Ok(png) => png,
-                    Err(e) => return Err(e.to_string()),
-                };
-
-                let chunk = match png.remove_chunk(&remove_cmd.chunk_type().to_string()) {
-                    Ok(chunk) => chunk,
-                    Err(e) => return Err(e.to_string()),
-                };
-
-                Ok(OperationResult::RemovedChunk(chunk))
+        }
+        Commands::Text(TextCommand::Set(set_cmd)) => {
+            let mut png = Png::try_from(read_file(set_cmd.file())?.as_slice())?;
+            let entry: TextEntry = if set_cmd.international() {
+                let mut entry = InternationalTextChunk::new(set_cmd.keyword(), set_cmd.text())?;
+                entry.compressed = set_cmd.compress();
+                entry.language_tag = set_cmd.lang().unwrap_or_default().to_string();
+                entry.translated_keyword = set_cmd.translated().unwrap_or_default().to_string();
+                entry.into()
+            } else if set_cmd.compress() {
+                CompressedTextChunk::new(set_cmd.keyword(), set_cmd.text())?.into()
+            } else {
+                TextChunk::new(set_cmd.keyword(), set_cmd.text())?.into()
+            };
+            png.set_text(&entry)?;
+            write_output(&png, set_cmd.file(), set_cmd.destination())?;
+            Ok(OperationResult::EncodedPng(png))
+        }
+        Commands::Text(TextCommand::List(list_cmd)) => {
+            let png = Png::try_from(read_file(list_cmd.file())?.as_slice())?;
+            let (entries, diagnostics) = png.text_entries();
+            Ok(OperationResult::TextEntries {
+                entries,
+                diagnostics,
+            })
+        }
+        Commands::Text(TextCommand::Remove(remove_cmd)) => {
+            let mut png = Png::try_from(read_file(remove_cmd.file())?.as_slice())?;
+            let removed = png.remove_text(remove_cmd.keyword())?;
+            write_output(&png, remove_cmd.file(), remove_cmd.destination())?;
+            Ok(OperationResult::RemovedChunks(removed))
+        }
+        Commands::Exif(ExifCommand::Show(show_cmd)) => {
+            let png = Png::try_from(read_file(show_cmd.file())?.as_slice())?;
+            let exif = png
+                .exif()?
+                .ok_or_else(|| PngError::ChunkNotFound("eXIf".to_string()))?;
+            Ok(OperationResult::ExifTags(exif))
+        }
+        Commands::Exif(ExifCommand::Strip(strip_cmd)) => {
+            let mut png = Png::try_from(read_file(strip_cmd.file())?.as_slice())?;
+            let removed = png.remove_all_chunks("eXIf")?;
+            write_output(&png, strip_cmd.file(), strip_cmd.destination())?;
+            Ok(OperationResult::RemovedChunks(removed))
+        }
+        Commands::Exif(ExifCommand::RemoveTag(remove_cmd)) => {
+            let mut png = Png::try_from(read_file(remove_cmd.file())?.as_slice())?;
+            let mut exif: Exif = png
+                .exif()?
+                .ok_or_else(|| PngError::ChunkNotFound("eXIf".to_string()))?;
+            let removed: Vec<_> = remove_cmd
+                .tags()
+                .iter()
+                .flat_map(|&selector| exif.remove(selector))
+                .collect();
+            // Rewriting re-lays out the IFDs, which breaks offsets inside
+            // opaque tags such as MakerNote, so only do it when needed.
+            if removed.is_empty() {
+                return Err(PngError::ChunkNotFound("eXIf tags".to_string()));
             }
-            Commands::Print(print_cmd) => {
-                let png = match Png::try_from(print_cmd.data().as_slice()) {
-                    Ok(png) => png,
-                    Err(e) => return Err(e.to_string()),
-                };
-
-                let chunk = match png.chunk_by_type(&print_cmd.chunk_type().to_string()) {
-                    Some(chunk) => chunk,
-                    None => return Err("Chunk not found".into()),
-                };
-                println!("{}", chunk.chunk_type().to_string());
-
-                let mut stdout = io::stdout();
-                let result: Result<(), io::Error> = writeln!(
-                    stdout,
-                    "Executing print command\n and the chunk is === {}",
-                    chunk.chunk_type().to_string()
-                ); // Returns Result
-
-                Ok(OperationResult::PrintedInfo(result))
+            png.set_exif(&exif);
+            write_output(&png, remove_cmd.file(), remove_cmd.destination())?;
+            Ok(OperationResult::RemovedExifTags(removed))
+        }
+        Commands::Xmp(XmpCommand::Show(show_cmd)) => {
+            let png = Png::try_from(read_file(show_cmd.file())?.as_slice())?;
+            let xml = png.xmp()?.ok_or_else(missing_xmp)?;
+            Ok(OperationResult::DecodedMessage(xml))
+        }
+        Commands::Xmp(XmpCommand::Get(get_cmd)) => {
+            let png = Png::try_from(read_file(get_cmd.file())?.as_slice())?;
+            let xmp = Xmp::new(&png.xmp()?.ok_or_else(missing_xmp)?);
+            match xmp.property(get_cmd.property())? {
+                Some(value) => Ok(OperationResult::DecodedMessage(value)),
+                None => Err(PngError::ChunkNotFound(format!(
+                    "XMP {}",
+                    get_cmd.property()
+                ))),
             }
         }
+        Commands::Xmp(XmpCommand::Set(set_cmd)) => {
+            let mut png = Png::try_from(read_file(set_cmd.file())?.as_slice())?;
+            let mut xmp = png.xmp()?.map(|xml| Xmp::new(&xml)).unwrap_or_default();
+            xmp.set_property(set_cmd.property(), set_cmd.value())?;
+            png.set_xmp(&xmp.xml)?;
+            write_output(&png, set_cmd.file(), set_cmd.destination())?;
+            Ok(OperationResult::EncodedPng(png))
+        }
     }
+}
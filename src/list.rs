@@ -0,0 +1,181 @@
+use std::fmt::Write;
+
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use crate::png_ref::PngRef;
+
+/// One row of `pngme list`: where a chunk lives in the file and what its type says about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkSummary {
+    pub index: usize,
+    /// Byte offset of the chunk's length field from the start of the file.
+    pub offset: usize,
+    pub chunk_type: ChunkType,
+    pub length: u32,
+    pub crc: u32,
+    pub crc_ok: bool,
+}
+
+impl ChunkSummary {
+    /// Summarises every chunk of `png` in file order.
+    pub fn from_png(png: &Png) -> Vec<ChunkSummary> {
+        summarise(png.chunks().iter().map(|chunk| {
+            (
+                chunk.chunk_type(),
+                chunk.length(),
+                chunk.crc(),
+                chunk.crc_is_valid(),
+            )
+        }))
+    }
+
+    /// Summarises every chunk of a borrowed `png` in file order.
+    pub fn from_png_ref(png: &PngRef<'_>) -> Vec<ChunkSummary> {
+        summarise(png.chunks().iter().map(|chunk| {
+            (
+                chunk.chunk_type(),
+                chunk.length(),
+                chunk.crc(),
+                chunk.crc_is_valid(),
+            )
+        }))
+    }
+}
+
+fn summarise(chunks: impl Iterator<Item = (ChunkType, u32, u32, bool)>) -> Vec<ChunkSummary> {
+    let mut offset = Png::STANDARD_HEADER.len();
+    chunks
+        .enumerate()
+        .map(|(index, (chunk_type, length, crc, crc_ok))| {
+            let summary = ChunkSummary {
+                index,
+                offset,
+                chunk_type,
+                length,
+                crc,
+                crc_ok,
+            };
+            offset += 12 + length as usize;
+            summary
+        })
+        .collect()
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Renders the summaries as a human readable table.
+pub fn format_table(summaries: &[ChunkSummary]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:>5}  {:>10}  {:<4}  {:>10}  {:<10}  {:<6}  {:<8}  {:<6}  {:<8}  SAFE TO COPY",
+        "INDEX", "OFFSET", "TYPE", "LENGTH", "CRC", "CRC OK", "CRITICAL", "PUBLIC", "RESERVED",
+    );
+    for s in summaries {
+        let _ = writeln!(
+            out,
+            "{:>5}  {:>10}  {:<4}  {:>10}  {:#010x}  {:<6}  {:<8}  {:<6}  {:<8}  {}",
+            s.index,
+            s.offset,
+            s.chunk_type,
+            s.length,
+            s.crc,
+            yes_no(s.crc_ok),
+            yes_no(s.chunk_type.is_critical()),
+            yes_no(s.chunk_type.is_public()),
+            if s.chunk_type.is_reserved_bit_valid() {
+                "ok"
+            } else {
+                "set"
+            },
+            yes_no(s.chunk_type.is_safe_to_copy()),
+        );
+    }
+    out
+}
+
+/// Renders the summaries as a JSON array, one object per chunk.
+pub fn format_json(summaries: &[ChunkSummary]) -> String {
+    let rows: Vec<String> = summaries
+        .iter()
+        .map(|s| {
+            format!(
+                "{{\"index\":{},\"offset\":{},\"type\":\"{}\",\"length\":{},\"crc\":{},\"crc_ok\":{},\"critical\":{},\"public\":{},\"reserved_bit_valid\":{},\"safe_to_copy\":{}}}",
+                s.index,
+                s.offset,
+                s.chunk_type,
+                s.length,
+                s.crc,
+                s.crc_ok,
+                s.chunk_type.is_critical(),
+                s.chunk_type.is_public(),
+                s.chunk_type.is_reserved_bit_valid(),
+                s.chunk_type.is_safe_to_copy(),
+            )
+        })
+        .collect();
+    format!("[{}]", rows.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("FrSt").unwrap(), b"first".to_vec()),
+            Chunk::new(ChunkType::from_str("miDl").unwrap(), b"middle".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_summary_offsets() {
+        let summaries = ChunkSummary::from_png(&testing_png());
+        let offsets: Vec<usize> = summaries.iter().map(|s| s.offset).collect();
+        assert_eq!(offsets, [8, 8 + 17, 8 + 17 + 18]);
+        assert!(summaries.iter().all(|s| s.crc_ok));
+        assert_eq!(summaries[1].length, 6);
+    }
+
+    #[test]
+    fn test_summary_from_png_ref() {
+        let png = testing_png();
+        let bytes = png.as_bytes();
+        let png_ref = PngRef::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(
+            ChunkSummary::from_png_ref(&png_ref),
+            ChunkSummary::from_png(&png)
+        );
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = format_table(&ChunkSummary::from_png(&testing_png()));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("OFFSET"));
+        assert!(lines[2].contains("miDl"));
+    }
+
+    #[test]
+    fn test_format_json() {
+        let summaries = ChunkSummary::from_png(&testing_png());
+        let json = format_json(&summaries[2..]);
+        assert_eq!(
+            json,
+            format!(
+                "[{{\"index\":2,\"offset\":43,\"type\":\"IEND\",\"length\":0,\"crc\":{},\"crc_ok\":true,\"critical\":true,\"public\":true,\"reserved_bit_valid\":true,\"safe_to_copy\":false}}]",
+                summaries[2].crc
+            )
+        );
+    }
+}
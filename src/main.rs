@@ -1,31 +1,120 @@
 use std::{env, process};
 
-use pngme::{args::Config, operation};
+use pngme::{
+    args::Config, commands::OperationResult, exif, list, operation, palette, validate::Severity,
+};
 // pub type Error = Box<dyn std::error::Error>;
 // pub type Result<T> = std::result::Result<T, Error>;
 
-
-fn main(){
+fn main() {
     let args: Vec<String> = env::args().collect();
 
-    let config: Config = Config::build(&args).unwrap_or_else(|err| {
-        eprintln!("Error building config: {}", err);
-        process::exit(1);
-    });
-    let _operation_result = operation(&config).unwrap_or_else(|err| {
+    // clap prints help, version and usage errors itself and picks the exit code.
+    let config: Config = Config::build(&args).unwrap_or_else(|err| err.exit());
+    let operation_result = operation(&config).unwrap_or_else(|err| {
         eprintln!("Error : {}", err);
         process::exit(1);
     });
 
-
+    match operation_result {
+        OperationResult::DecodedMessage(message) => println!("{}", message),
+        OperationResult::HeaderInfo(ihdr) => println!("{}", ihdr),
+        OperationResult::FullInfo {
+            header,
+            palette,
+            color,
+            ancillary,
+            last_modified,
+            diagnostics,
+        } => {
+            println!("{}", header);
+            if let Some(palette) = palette {
+                println!("palette: {} entries", palette.entries.len());
+            }
+            if !color.is_empty() {
+                print!("{}", color);
+            }
+            print!("{}", ancillary);
+            if let Some(time) = last_modified {
+                println!("last modified: {}", time);
+            }
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic);
+            }
+        }
+        OperationResult::Validated(diagnostics) => {
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic);
+            }
+            if diagnostics.is_empty() {
+                println!("no problems found");
+            }
+            if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                process::exit(1);
+            }
+        }
+        OperationResult::Compared(None) => println!("images decode identically"),
+        OperationResult::Compared(Some(difference)) => {
+            println!("images differ: {}", difference);
+            process::exit(1);
+        }
+        OperationResult::ChunkList { chunks, json } => {
+            if json {
+                println!("{}", list::format_json(&chunks));
+            } else {
+                print!("{}", list::format_table(&chunks));
+            }
+        }
+        OperationResult::PaletteInfo {
+            palette,
+            transparency,
+            json,
+        } => {
+            if json {
+                println!("{}", palette::format_json(&palette, transparency.as_ref()));
+            } else {
+                print!("{}", palette::format_table(&palette, transparency.as_ref()));
+            }
+        }
+        OperationResult::ColorInfo(info) => print!("{}", info),
+        OperationResult::ExtractedProfile { path, profile } => println!(
+            "wrote {} ({} bytes) to {}",
+            profile.name,
+            profile.profile.len(),
+            path.display()
+        ),
+        OperationResult::ExifTags(tags) => print!("{}", exif::format_table(&tags)),
+        OperationResult::RemovedExifTags(removed) => {
+            for entry in &removed {
+                println!(
+                    "removed {} {:#06x} {}",
+                    entry.ifd,
+                    entry.tag,
+                    entry.name().unwrap_or("-")
+                );
+            }
+        }
+        OperationResult::TextEntries {
+            entries,
+            diagnostics,
+        } => {
+            for entry in &entries {
+                println!("{}", entry);
+            }
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic);
+            }
+        }
+        _ => {}
+    }
 
     // let _ = run(config)?;
 
-    // another way to handle error 
+    // another way to handle error
     //     if let Err(e) = run(config) {
     //     println!("Application error: {e}");
     //     process::exit(1);
     // }
 
     // Ok(())
-}
\ No newline at end of file
+}
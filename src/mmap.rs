@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::error::{PngError, Result};
+use crate::png::Png;
+use crate::png_ref::PngRef;
+
+/// A PNG file mapped into memory for read-only access. Chunks are read
+/// straight from the mapping through `png_ref`, without copying the file.
+#[derive(Debug)]
+pub struct MappedPng {
+    map: Mmap,
+}
+
+impl MappedPng {
+    /// Maps `path` and checks the signature. The chunks are not parsed yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<MappedPng> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is only ever read. Truncating or rewriting the
+        // file while it is mapped is the caller's responsibility, as with
+        // any memory-mapped file.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = &Png::STANDARD_HEADER;
+        if !map.starts_with(header) {
+            let end = map.len().min(header.len());
+            return Err(PngError::InvalidSignature(map[..end].to_vec()));
+        }
+        Ok(MappedPng { map })
+    }
+
+    /// The whole file, signature included.
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Parses the chunk layout. Chunk data borrows from the mapping.
+    pub fn png_ref(&self) -> Result<PngRef<'_>> {
+        PngRef::try_from(self.bytes())
+    }
+}
+
+impl Png {
+    /// Opens `path` as a memory map instead of reading it into memory, for
+    /// commands that only inspect the file.
+    pub fn open_mmap(path: impl AsRef<Path>) -> Result<MappedPng> {
+        MappedPng::open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn temp_file(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join("pngme_mmap_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_open_mmap() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("FrSt").unwrap(), b"first".to_vec()),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"secret".to_vec()),
+        ]);
+        let path = temp_file("open.png", &png.as_bytes());
+
+        let mapped = Png::open_mmap(&path).unwrap();
+        assert_eq!(mapped.bytes(), png.as_bytes());
+        let png_ref = mapped.png_ref().unwrap();
+        assert_eq!(png_ref.chunks().len(), 2);
+        assert_eq!(
+            png_ref
+                .chunk_by_type("ruSt")
+                .unwrap()
+                .data_as_str()
+                .unwrap(),
+            "secret"
+        );
+    }
+
+    #[test]
+    fn test_open_mmap_errors() {
+        let path = temp_file("not_a_png.png", b"GIF89a");
+        assert!(matches!(
+            Png::open_mmap(&path),
+            Err(PngError::InvalidSignature(_))
+        ));
+
+        let path = temp_file("empty.png", b"");
+        assert!(matches!(
+            Png::open_mmap(&path),
+            Err(PngError::InvalidSignature(_))
+        ));
+
+        let missing = std::env::temp_dir().join("pngme_mmap_tests/missing.png");
+        assert!(matches!(Png::open_mmap(missing), Err(PngError::Io(_))));
+    }
+}
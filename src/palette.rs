@@ -0,0 +1,402 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_data::{check_type, invalid, read_u16};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::ihdr::{ColorType, Ihdr};
+use crate::png::Png;
+
+/// One RGB colour of a `PLTE` chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl fmt::Display for PaletteEntry {
+    /// Hex colour, e.g. `#ff8800`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+/// The decoded contents of the `PLTE` chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub entries: Vec<PaletteEntry>,
+}
+
+impl Palette {
+    pub const MAX_ENTRIES: usize = 256;
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("PLTE").unwrap()
+    }
+
+    /// Checks the entry count: 1 to 256, and no more than the bit depth can
+    /// index for indexed images. Grayscale images may not have a palette.
+    pub fn validate(&self, ihdr: Option<&Ihdr>) -> Result<()> {
+        let len = self.entries.len();
+        if len == 0 || len > Palette::MAX_ENTRIES {
+            return Err(invalid(
+                Palette::chunk_type(),
+                format!("expected 1 to 256 entries, got {}", len),
+            ));
+        }
+        let Some(ihdr) = ihdr else {
+            return Ok(());
+        };
+        match ihdr.color_type {
+            ColorType::Grayscale | ColorType::GrayscaleAlpha => Err(invalid(
+                Palette::chunk_type(),
+                format!("{} images must not have a palette", ihdr.color_type),
+            )),
+            ColorType::Indexed if len > 1 << ihdr.bit_depth => Err(invalid(
+                Palette::chunk_type(),
+                format!(
+                    "{} entries do not fit a bit depth of {}",
+                    len, ihdr.bit_depth
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.validate(None)?;
+        let data = self
+            .entries
+            .iter()
+            .flat_map(|entry| [entry.red, entry.green, entry.blue])
+            .collect();
+        Ok(Chunk::new(Palette::chunk_type(), data))
+    }
+}
+
+impl TryFrom<&Chunk> for Palette {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Palette::chunk_type())?;
+        let data = chunk.data();
+        if !data.len().is_multiple_of(3) {
+            return Err(invalid(
+                Palette::chunk_type(),
+                format!("length {} is not a multiple of 3", data.len()),
+            ));
+        }
+        let palette = Palette {
+            entries: data
+                .chunks_exact(3)
+                .map(|rgb| PaletteEntry {
+                    red: rgb[0],
+                    green: rgb[1],
+                    blue: rgb[2],
+                })
+                .collect(),
+        };
+        palette.validate(None)?;
+        Ok(palette)
+    }
+}
+
+/// The decoded contents of the `tRNS` chunk, whose layout depends on the colour type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transparency {
+    /// Alpha for the first palette entries. Later entries are opaque.
+    Indexed(Vec<u8>),
+    /// The gray level drawn fully transparent.
+    Grayscale(u16),
+    /// The colour drawn fully transparent.
+    Rgb { red: u16, green: u16, blue: u16 },
+}
+
+impl Transparency {
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("tRNS").unwrap()
+    }
+
+    /// Decodes `chunk` for an image described by `ihdr`. Indexed images need
+    /// their palette to check the number of alpha values.
+    pub fn from_chunk(chunk: &Chunk, ihdr: &Ihdr, palette: Option<&Palette>) -> Result<Self> {
+        let chunk_type = Transparency::chunk_type();
+        check_type(chunk, chunk_type)?;
+        let data = chunk.data();
+        let expect_length = |length: usize| {
+            if data.len() != length {
+                return Err(invalid(
+                    chunk_type,
+                    format!(
+                        "expected {} bytes for {} images, got {}",
+                        length,
+                        ihdr.color_type,
+                        data.len()
+                    ),
+                ));
+            }
+            Ok(())
+        };
+        let sample = |i: usize| -> Result<u16> {
+            let value = read_u16(data, i);
+            if ihdr.bit_depth < 16 && value >= 1 << ihdr.bit_depth {
+                return Err(invalid(
+                    chunk_type,
+                    format!(
+                        "sample {} does not fit a bit depth of {}",
+                        value, ihdr.bit_depth
+                    ),
+                ));
+            }
+            Ok(value)
+        };
+
+        match ihdr.color_type {
+            ColorType::Indexed => {
+                let palette = palette.ok_or_else(|| PngError::ChunkNotFound("PLTE".to_string()))?;
+                if data.len() > palette.entries.len() {
+                    return Err(invalid(
+                        chunk_type,
+                        format!(
+                            "{} alpha values for a palette of {} entries",
+                            data.len(),
+                            palette.entries.len()
+                        ),
+                    ));
+                }
+                Ok(Transparency::Indexed(data.to_vec()))
+            }
+            ColorType::Grayscale => {
+                expect_length(2)?;
+                Ok(Transparency::Grayscale(sample(0)?))
+            }
+            ColorType::Rgb => {
+                expect_length(6)?;
+                Ok(Transparency::Rgb {
+                    red: sample(0)?,
+                    green: sample(2)?,
+                    blue: sample(4)?,
+                })
+            }
+            ColorType::GrayscaleAlpha | ColorType::Rgba => Err(invalid(
+                chunk_type,
+                format!("{} images must not have a tRNS chunk", ihdr.color_type),
+            )),
+        }
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        let data = match self {
+            Transparency::Indexed(alphas) => alphas.clone(),
+            Transparency::Grayscale(gray) => gray.to_be_bytes().to_vec(),
+            Transparency::Rgb { red, green, blue } => [red, green, blue]
+                .iter()
+                .flat_map(|sample| sample.to_be_bytes())
+                .collect(),
+        };
+        Chunk::new(Transparency::chunk_type(), data)
+    }
+
+    /// Alpha of palette entry `index`: 255 unless the chunk lists one.
+    pub fn alpha(&self, index: usize) -> u8 {
+        match self {
+            Transparency::Indexed(alphas) => alphas.get(index).copied().unwrap_or(255),
+            _ => 255,
+        }
+    }
+}
+
+impl Png {
+    /// Decodes the `PLTE` chunk, if there is one, checking it against the header.
+    pub fn palette(&self) -> Result<Option<Palette>> {
+        let Some(chunk) = self.chunk_by_type("PLTE") else {
+            return Ok(None);
+        };
+        let palette = Palette::try_from(chunk)?;
+        palette.validate(Some(&self.header_info()?))?;
+        Ok(Some(palette))
+    }
+
+    /// Decodes the `tRNS` chunk, if there is one, according to the header's colour type.
+    pub fn transparency(&self) -> Result<Option<Transparency>> {
+        let Some(chunk) = self.chunk_by_type("tRNS") else {
+            return Ok(None);
+        };
+        let ihdr = self.header_info()?;
+        let palette = self.palette()?;
+        Transparency::from_chunk(chunk, &ihdr, palette.as_ref()).map(Some)
+    }
+}
+
+/// Renders the palette as one `index  #rrggbb  alpha` line per entry.
+pub fn format_table(palette: &Palette, transparency: Option<&Transparency>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{:>5}  {:<7}  ALPHA", "INDEX", "COLOUR");
+    for (index, entry) in palette.entries.iter().enumerate() {
+        let alpha = transparency.map_or(255, |t| t.alpha(index));
+        let _ = writeln!(out, "{:>5}  {}  {}", index, entry, alpha);
+    }
+    out
+}
+
+/// Renders the palette as a JSON array, one object per entry.
+pub fn format_json(palette: &Palette, transparency: Option<&Transparency>) -> String {
+    let rows: Vec<String> = palette
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            format!(
+                "{{\"index\":{},\"hex\":\"{}\",\"red\":{},\"green\":{},\"blue\":{},\"alpha\":{}}}",
+                index,
+                entry,
+                entry.red,
+                entry.green,
+                entry.blue,
+                transparency.map_or(255, |t| t.alpha(index)),
+            )
+        })
+        .collect();
+    format!("[{}]", rows.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ihdr::InterlaceMethod;
+
+    fn ihdr(color_type: ColorType, bit_depth: u8) -> Ihdr {
+        Ihdr {
+            width: 1,
+            height: 1,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+    }
+
+    fn testing_palette() -> Palette {
+        Palette {
+            entries: vec![
+                PaletteEntry {
+                    red: 255,
+                    green: 136,
+                    blue: 0,
+                },
+                PaletteEntry {
+                    red: 0,
+                    green: 0,
+                    blue: 0,
+                },
+                PaletteEntry {
+                    red: 1,
+                    green: 2,
+                    blue: 3,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_palette_round_trip() {
+        let palette = testing_palette();
+        let chunk = palette.to_chunk().unwrap();
+        assert_eq!(chunk.data(), [255, 136, 0, 0, 0, 0, 1, 2, 3]);
+        assert_eq!(Palette::try_from(&chunk).unwrap(), palette);
+        assert_eq!(palette.entries[0].to_string(), "#ff8800");
+    }
+
+    #[test]
+    fn test_palette_errors() {
+        let chunk = Chunk::new(Palette::chunk_type(), vec![0; 4]);
+        assert!(Palette::try_from(&chunk).is_err());
+        let chunk = Chunk::new(Palette::chunk_type(), Vec::new());
+        assert!(Palette::try_from(&chunk).is_err());
+        let chunk = Chunk::new(Palette::chunk_type(), vec![0; 3 * 257]);
+        assert!(Palette::try_from(&chunk).is_err());
+
+        let palette = testing_palette();
+        assert!(palette.validate(Some(&ihdr(ColorType::Indexed, 2))).is_ok());
+        assert!(palette
+            .validate(Some(&ihdr(ColorType::Indexed, 1)))
+            .is_err());
+        assert!(palette.validate(Some(&ihdr(ColorType::Rgb, 8))).is_ok());
+        assert!(palette
+            .validate(Some(&ihdr(ColorType::Grayscale, 8)))
+            .is_err());
+    }
+
+    #[test]
+    fn test_transparency() {
+        let palette = testing_palette();
+        let indexed = ihdr(ColorType::Indexed, 8);
+        let chunk = Chunk::new(Transparency::chunk_type(), vec![0, 128]);
+        let trns = Transparency::from_chunk(&chunk, &indexed, Some(&palette)).unwrap();
+        assert_eq!(trns, Transparency::Indexed(vec![0, 128]));
+        assert_eq!([trns.alpha(0), trns.alpha(1), trns.alpha(2)], [0, 128, 255]);
+        assert_eq!(trns.to_chunk().data(), chunk.data());
+
+        let chunk = Chunk::new(Transparency::chunk_type(), vec![0; 4]);
+        assert!(Transparency::from_chunk(&chunk, &indexed, Some(&palette)).is_err());
+        assert!(Transparency::from_chunk(&chunk, &indexed, None).is_err());
+
+        let chunk = Chunk::new(Transparency::chunk_type(), vec![0, 1, 0, 2, 0, 3]);
+        let trns = Transparency::from_chunk(&chunk, &ihdr(ColorType::Rgb, 8), None).unwrap();
+        assert_eq!(
+            trns,
+            Transparency::Rgb {
+                red: 1,
+                green: 2,
+                blue: 3
+            }
+        );
+        assert_eq!(trns.to_chunk().data(), chunk.data());
+
+        let chunk = Chunk::new(Transparency::chunk_type(), vec![0, 4]);
+        let gray = ihdr(ColorType::Grayscale, 2);
+        assert!(Transparency::from_chunk(&chunk, &gray, None).is_err());
+        let gray = ihdr(ColorType::Grayscale, 4);
+        assert_eq!(
+            Transparency::from_chunk(&chunk, &gray, None).unwrap(),
+            Transparency::Grayscale(4)
+        );
+        assert!(Transparency::from_chunk(&chunk, &ihdr(ColorType::Rgba, 8), None).is_err());
+    }
+
+    #[test]
+    fn test_png_palette() {
+        let png = Png::from_chunks(vec![
+            ihdr(ColorType::Indexed, 8).to_chunk(),
+            testing_palette().to_chunk().unwrap(),
+            Chunk::new(Transparency::chunk_type(), vec![7]),
+        ]);
+        assert_eq!(png.palette().unwrap(), Some(testing_palette()));
+        assert_eq!(
+            png.transparency().unwrap(),
+            Some(Transparency::Indexed(vec![7]))
+        );
+
+        let png = Png::from_chunks(vec![ihdr(ColorType::Rgb, 8).to_chunk()]);
+        assert_eq!(png.palette().unwrap(), None);
+        assert_eq!(png.transparency().unwrap(), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let palette = testing_palette();
+        let trns = Transparency::Indexed(vec![0]);
+        let table = format_table(&palette, Some(&trns));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "    0  #ff8800  0");
+
+        let json = format_json(&palette, None);
+        assert!(json.starts_with(
+            "[{\"index\":0,\"hex\":\"#ff8800\",\"red\":255,\"green\":136,\"blue\":0,\"alpha\":255}"
+        ));
+    }
+}
@@ -1,63 +1,260 @@
 use core::fmt;
-use std::{clone, path::Display};
-
-// use anyhow::Ok;
-
-// use anyhow::Ok;
+use std::io::Write;
 
 use crate::{
-    chunk::{self, Chunk},
-    chunk_type::{self, ChunkType},
+    chunk::Chunk,
+    chunk_ref::ChunkRef,
+    chunk_type::ChunkType,
+    error::{PngError, Result},
+    ihdr::Ihdr,
+    image::check_image_len,
+    png_ref::PngRef,
+    validate::{check_chunk_order, Diagnostic},
+    writer::PngWriter,
+    zlib,
 };
+/// What to do when a chunk's stored CRC does not match its contents.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CrcPolicy {
+    /// Fail with `PngError::CrcMismatch`.
+    #[default]
+    Strict,
+    /// Keep the chunk and its stored CRC, and record a warning on the `Png`
+    /// or `ChunkReader`.
+    Warn,
+    /// Replace the stored CRC with a freshly computed one.
+    Recompute,
+}
+
+/// Options controlling how `Png::parse` treats the input.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ParseOptions {
+    pub crc_policy: CrcPolicy,
+}
+
+impl ParseOptions {
+    pub fn crc_policy(mut self, crc_policy: CrcPolicy) -> ParseOptions {
+        self.crc_policy = crc_policy;
+        self
+    }
+
+    /// Builds a chunk from its stored parts, applying the CRC policy. A
+    /// mismatch tolerated under `CrcPolicy::Warn` is pushed onto `warnings`.
+    pub(crate) fn build_chunk(
+        &self,
+        chunk_index: usize,
+        chunk_type: ChunkType,
+        data: Vec<u8>,
+        crc: u32,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Chunk> {
+        if self.crc_policy == CrcPolicy::Recompute {
+            return Ok(Chunk::new(chunk_type, data));
+        }
+        let chunk = Chunk::with_stored_crc(chunk_type, data, crc);
+        if let Err(error) = chunk.verify_crc() {
+            if self.crc_policy == CrcPolicy::Strict {
+                return Err(error);
+            }
+            warnings.push(ParseWarning { chunk_index, error });
+        }
+        Ok(chunk)
+    }
+}
+
+/// A problem found while parsing that did not stop the parse.
+#[derive(Debug)]
+pub struct ParseWarning {
+    pub chunk_index: usize,
+    pub error: PngError,
+}
+
 #[derive(Debug)]
 pub struct Png {
     data: Vec<Chunk>,
+    warnings: Vec<ParseWarning>,
 }
 
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
 
-    fn from_chunks(chunks: Vec<Chunk>) -> Png {
-        Png { data: chunks }
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
+        Png {
+            data: chunks,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Parses `bytes` as a PNG file, handling bad CRCs according to `options`.
+    pub fn parse(bytes: &[u8], options: &ParseOptions) -> Result<Png> {
+        Png::from_chunk_refs(PngRef::try_from(bytes)?.chunks(), options)
     }
 
+    pub(crate) fn from_chunk_refs(chunks: &[ChunkRef], options: &ParseOptions) -> Result<Png> {
+        let mut data: Vec<Chunk> = Vec::with_capacity(chunks.len());
+        let mut warnings = Vec::new();
+
+        for (chunk_index, chunk) in chunks.iter().enumerate() {
+            data.push(options.build_chunk(
+                chunk_index,
+                chunk.chunk_type(),
+                chunk.data().to_vec(),
+                chunk.crc(),
+                &mut warnings,
+            )?);
+        }
+
+        Ok(Png { data, warnings })
+    }
+
+    /// Problems tolerated while parsing, such as CRC mismatches under `CrcPolicy::Warn`.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Adds `chunk` at the end of the image, keeping `IEND` as the last chunk.
     pub fn append_chunk(&mut self, chunk: Chunk) {
-        self.data.push(chunk)
+        match self.position_of("IEND") {
+            Some(pos) => self.data.insert(pos, chunk),
+            None => self.data.push(chunk),
+        }
     }
 
-    fn header(&self) -> &[u8; 8] {
-        self.data[0].data().try_into().expect("size 8")
+    /// Inserts `chunk` at `index`, shifting later chunks back.
+    pub fn insert_chunk_at(&mut self, index: usize, chunk: Chunk) -> Result<()> {
+        if index > self.data.len() {
+            return Err(PngError::IndexOutOfRange {
+                index,
+                len: self.data.len(),
+            });
+        }
+        self.data.insert(index, chunk);
+        Ok(())
     }
-    fn chunks(&self) -> &[Chunk] {
-        &self.data
+
+    /// Inserts `chunk` right before the first chunk of type `chunk_type`.
+    pub fn insert_before(&mut self, chunk_type: &str, chunk: Chunk) -> Result<()> {
+        let pos = self
+            .position_of(chunk_type)
+            .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        self.data.insert(pos, chunk);
+        Ok(())
     }
-    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
-        match self
+
+    /// Inserts `chunk` right after the last chunk of type `chunk_type`, so
+    /// runs such as consecutive `IDAT`s stay together.
+    pub fn insert_after(&mut self, chunk_type: &str, chunk: Chunk) -> Result<()> {
+        let pos = self
             .data
             .iter()
-            .find(|&chunk| chunk.chunk_type().to_string() == chunk_type.to_string())
-        {
-            Some(chunk) => Some(chunk),
-            None => None,
+            .rposition(|chunk| chunk.chunk_type().to_string() == chunk_type)
+            .ok_or_else(|| PngError::ChunkNotFound(chunk_type.to_string()))?;
+        self.data.insert(pos + 1, chunk);
+        Ok(())
+    }
+
+    /// Inserts `chunk` before the first `IDAT`, for chunks the spec requires
+    /// ahead of the image data. Without image data it is appended instead.
+    pub fn insert_before_image_data(&mut self, chunk: Chunk) {
+        match self.position_of("IDAT") {
+            Some(pos) => self.data.insert(pos, chunk),
+            None => self.append_chunk(chunk),
+        }
+    }
+
+    fn position_of(&self, chunk_type: &str) -> Option<usize> {
+        self.data
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
+    /// Decodes and validates the `IHDR` chunk, which must come first.
+    pub fn header_info(&self) -> Result<Ihdr> {
+        match self.data.first() {
+            Some(chunk) => Ihdr::try_from(chunk),
+            None => Err(PngError::ChunkNotFound("IHDR".to_string())),
         }
     }
-    pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk, String> {
-        if let Some(pos) = self
+
+    /// Concatenates the `IDAT` payloads in file order and inflates them into
+    /// the filtered scanlines, each starting with its filter type byte. The
+    /// amount of data must match what `IHDR` describes.
+    pub fn image_data(&self) -> Result<Vec<u8>> {
+        let header = self.header_info()?;
+        let mut idats = self
             .data
             .iter()
-            .position(|chunk| chunk.chunk_type().to_string() == chunk_type.to_string())
-        {
+            .filter(|chunk| chunk.chunk_type().to_string() == "IDAT")
+            .peekable();
+        if idats.peek().is_none() {
+            return Err(PngError::ChunkNotFound("IDAT".to_string()));
+        }
+        // Capped so a small stream cannot be inflated into gigabytes.
+        let expected_len = check_image_len(header.image_data_len()?, "scanlines")?;
+        let stream: Vec<u8> = idats.flat_map(|chunk| chunk.data()).copied().collect();
+        zlib::inflate_image_data(&stream, expected_len)
+    }
+
+    /// Checks the image against the PNG structure rules. CRC problems recorded
+    /// while parsing are reported as errors too.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self
+            .warnings
+            .iter()
+            .map(|warning| Diagnostic::error(Some(warning.chunk_index), warning.error.to_string()))
+            .collect();
+        diagnostics.extend(check_chunk_order(&self.data));
+        diagnostics.sort_by_key(|diagnostic| diagnostic.chunk_index);
+        diagnostics
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.data
+    }
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
+        self.data
+            .iter()
+            .find(|&chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+    pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
+        if let Some(pos) = self.position_of(chunk_type) {
             Ok(self.data.remove(pos))
         } else {
-            Err(format!("Chunk type {} not found", chunk_type))
+            Err(PngError::ChunkNotFound(chunk_type.to_string()))
+        }
+    }
+    /// Removes every chunk of type `chunk_type`, returning them in file order.
+    pub fn remove_all_chunks(&mut self, chunk_type: &str) -> Result<Vec<Chunk>> {
+        let removed =
+            self.remove_chunks_where(|chunk| chunk.chunk_type().to_string() == chunk_type);
+        if removed.is_empty() {
+            return Err(PngError::ChunkNotFound(chunk_type.to_string()));
         }
+        Ok(removed)
+    }
+    /// Removes every chunk matching `predicate`, returning them in file order.
+    pub fn remove_chunks_where(&mut self, mut predicate: impl FnMut(&Chunk) -> bool) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self.data)
+            .into_iter()
+            .partition(|chunk| predicate(chunk));
+        self.data = kept;
+        removed
+    }
+    /// Streams the image to `writer` without building the whole file in memory first.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut writer = PngWriter::new(writer)?;
+        for chunk in &self.data {
+            writer.write_chunk(chunk)?;
+        }
+        writer.finish()?;
+        Ok(())
     }
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut bytes: Vec<u8> = Vec::new();
         for byte in Png::STANDARD_HEADER {
             bytes.push(byte);
         }
-        bytes.extend(self.data.iter().map(|chunk| chunk.as_bytes()).flatten());
+        bytes.extend(self.data.iter().flat_map(|chunk| chunk.as_bytes()));
         bytes
     }
 }
@@ -72,45 +269,19 @@ impl fmt::Display for Png {
 }
 
 impl TryFrom<&[u8]> for Png {
-    type Error = String;
-
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        if value[..8] != Png::STANDARD_HEADER {
-            return Err("The byte is not compatible with PNG Standards".to_string());
-        }
-
-        let mut data: Vec<Chunk> = Vec::new();
-        let mut tmp = 8;
-        // data.push(Chunk::header_to_chunk());
-
-        while tmp < value.len() {
-            let chunk_length = u32::from_be_bytes(value[tmp..(tmp + 4)].try_into().unwrap());
-            tmp += 4;
-
-            let chunk_type_u8 = &value[tmp..tmp + 4];
-            tmp += 4;
-            let chunk_data = &value[tmp..(tmp + chunk_length as usize)];
+    type Error = PngError;
 
-            let chunk_type = ChunkType::new(chunk_type_u8.try_into().unwrap());
-
-            let chunk = Chunk::new(chunk_type, chunk_data.to_vec());
-            data.push(chunk);
-            tmp += (chunk_length as usize) + 4
-        }
-
-        let png = Png { data: data };
-
-        Ok(png)
+    fn try_from(value: &[u8]) -> Result<Self> {
+        Png::parse(value, &ParseOptions::default())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use anyhow::Error;
-
     use super::*;
     use crate::chunk::Chunk;
     use crate::chunk_type::ChunkType;
+    use crate::ihdr::{ColorType, InterlaceMethod};
     use std::convert::TryFrom;
     use std::str::FromStr;
 
@@ -127,9 +298,7 @@ mod tests {
         Png::from_chunks(chunks)
     }
 
-    fn chunk_from_strings(chunk_type: &str, data: &str) -> Result<Chunk, anyhow::Error> {
-        use std::str::FromStr;
-
+    fn chunk_from_strings(chunk_type: &str, data: &str) -> Result<Chunk> {
         let chunk_type = ChunkType::from_str(chunk_type)?;
         let data: Vec<u8> = data.bytes().collect();
 
@@ -201,6 +370,113 @@ mod tests {
         assert!(png.is_err());
     }
 
+    fn testing_png_bytes() -> Vec<u8> {
+        Png::STANDARD_HEADER
+            .iter()
+            .copied()
+            .chain(
+                testing_chunks()
+                    .into_iter()
+                    .flat_map(|chunk| chunk.as_bytes()),
+            )
+            .collect()
+    }
+
+    #[test]
+    fn test_short_signature() {
+        let png = Png::try_from(&Png::STANDARD_HEADER[..5]);
+        assert!(matches!(png, Err(PngError::InvalidSignature(found)) if found.len() == 5));
+    }
+
+    #[test]
+    fn test_truncated_chunk_reports_position() {
+        let bytes = testing_png_bytes();
+        // Cut the CRC of the last chunk in half.
+        let png = Png::try_from(&bytes[..bytes.len() - 2]);
+
+        let last_offset = 8 + 32 + 30;
+        match png {
+            Err(PngError::TruncatedChunk {
+                chunk_index,
+                offset,
+                needed,
+                available,
+            }) => {
+                assert_eq!(chunk_index, 2);
+                assert_eq!(offset, last_offset);
+                assert_eq!(needed, 31);
+                assert_eq!(available, 29);
+            }
+            other => panic!("expected TruncatedChunk, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_length_overflow_reports_position() {
+        let mut bytes = testing_png_bytes();
+        // Declare a huge length for the second chunk.
+        bytes[8 + 32..8 + 32 + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let png = Png::try_from(bytes.as_ref());
+
+        assert!(matches!(
+            png,
+            Err(PngError::LengthOverflow {
+                chunk_index: 1,
+                offset: 40,
+                length: u32::MAX,
+                remaining: Some(_),
+            })
+        ));
+    }
+
+    #[test]
+    fn test_never_panics_on_truncated_input() {
+        let bytes = testing_png_bytes();
+        for end in 0..bytes.len() {
+            let _ = Png::try_from(&bytes[..end]);
+        }
+        for end in 0..PNG_FILE.len() {
+            let _ = Png::try_from(&PNG_FILE[..end]);
+        }
+    }
+
+    fn corrupted_png_bytes() -> Vec<u8> {
+        let mut bytes = testing_png_bytes();
+        // Flip a bit in the data of the second chunk.
+        bytes[8 + 32 + 8] ^= 1;
+        bytes
+    }
+
+    #[test]
+    fn test_strict_crc_policy_rejects_corruption() {
+        let png = Png::try_from(corrupted_png_bytes().as_ref());
+        assert!(matches!(
+            png,
+            Err(PngError::CrcMismatch { chunk_type, .. }) if chunk_type.to_string() == "miDl"
+        ));
+    }
+
+    #[test]
+    fn test_warn_crc_policy_keeps_stored_crc() {
+        let options = ParseOptions::default().crc_policy(CrcPolicy::Warn);
+        let png = Png::parse(&corrupted_png_bytes(), &options).unwrap();
+
+        assert_eq!(png.warnings().len(), 1);
+        assert_eq!(png.warnings()[0].chunk_index, 1);
+        let chunk = &png.chunks()[1];
+        assert!(!chunk.crc_is_valid());
+        assert_eq!(png.as_bytes(), corrupted_png_bytes());
+    }
+
+    #[test]
+    fn test_recompute_crc_policy_repairs_chunk() {
+        let options = ParseOptions::default().crc_policy(CrcPolicy::Recompute);
+        let png = Png::parse(&corrupted_png_bytes(), &options).unwrap();
+
+        assert!(png.warnings().is_empty());
+        assert!(png.chunks().iter().all(|chunk| chunk.crc_is_valid()));
+    }
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -226,6 +502,56 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "Message");
     }
 
+    fn testing_png_with_iend() -> Png {
+        let mut chunks = testing_chunks();
+        chunks.push(chunk_from_strings("IEND", "").unwrap());
+        Png::from_chunks(chunks)
+    }
+
+    fn chunk_types(png: &Png) -> Vec<String> {
+        png.chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_append_chunk_goes_before_iend() {
+        let mut png = testing_png_with_iend();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        assert_eq!(chunk_types(&png), ["FrSt", "miDl", "LASt", "TeSt", "IEND"]);
+    }
+
+    #[test]
+    fn test_insert_chunk_at() {
+        let mut png = testing_png_with_iend();
+        png.insert_chunk_at(1, chunk_from_strings("TeSt", "Message").unwrap())
+            .unwrap();
+        assert_eq!(chunk_types(&png), ["FrSt", "TeSt", "miDl", "LASt", "IEND"]);
+
+        let err = png.insert_chunk_at(9, chunk_from_strings("TeSt", "Message").unwrap());
+        assert!(matches!(
+            err,
+            Err(PngError::IndexOutOfRange { index: 9, len: 5 })
+        ));
+    }
+
+    #[test]
+    fn test_insert_before_and_after() {
+        let mut png = testing_png_with_iend();
+        png.insert_before("miDl", chunk_from_strings("BeFr", "before").unwrap())
+            .unwrap();
+        png.insert_after("miDl", chunk_from_strings("AfTr", "after").unwrap())
+            .unwrap();
+        assert_eq!(
+            chunk_types(&png),
+            ["FrSt", "BeFr", "miDl", "AfTr", "LASt", "IEND"]
+        );
+
+        let missing = png.insert_before("NoNe", chunk_from_strings("TeSt", "x").unwrap());
+        assert!(matches!(missing, Err(PngError::ChunkNotFound(_))));
+    }
+
     #[test]
     fn test_remove_first_chunk() {
         let mut png = testing_png();
@@ -235,6 +561,103 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_header_info() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let ihdr = png.header_info().unwrap();
+        assert_eq!(ihdr.width, 50);
+        assert_eq!(ihdr.height, 50);
+        assert_eq!(ihdr.bit_depth, 8);
+
+        let png = testing_png();
+        assert!(matches!(png.header_info(), Err(PngError::ChunkNotFound(_))));
+    }
+
+    #[test]
+    fn test_image_data() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let header = png.header_info().unwrap();
+        assert_eq!(
+            png.image_data().unwrap().len(),
+            header.image_data_len().unwrap()
+        );
+
+        // 2x2 8-bit grayscale, split across three IDAT chunks.
+        let scanlines = vec![0, 10, 20, 1, 30, 40];
+        let stream = zlib::compress(&scanlines);
+        let ihdr = Ihdr {
+            width: 2,
+            height: 2,
+            bit_depth: 8,
+            color_type: ColorType::Grayscale,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        };
+        let idat = |data: &[u8]| Chunk::new(ChunkType::from_str("IDAT").unwrap(), data.to_vec());
+        let iend = || Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new());
+        let png = Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            idat(&stream[..3]),
+            idat(&[]),
+            idat(&stream[3..]),
+            iend(),
+        ]);
+        assert_eq!(png.image_data().unwrap(), scanlines);
+
+        let png = Png::from_chunks(vec![ihdr.to_chunk(), idat(&stream[3..]), iend()]);
+        assert!(matches!(
+            png.image_data(),
+            Err(PngError::CorruptImageData(_))
+        ));
+        let mut bad_checksum = stream.clone();
+        *bad_checksum.last_mut().unwrap() ^= 0xff;
+        let png = Png::from_chunks(vec![ihdr.to_chunk(), idat(&bad_checksum), iend()]);
+        assert!(matches!(
+            png.image_data(),
+            Err(PngError::Adler32Mismatch { .. })
+        ));
+        let png = Png::from_chunks(vec![ihdr.to_chunk(), iend()]);
+        assert!(matches!(
+            png.image_data(),
+            Err(PngError::ChunkNotFound(chunk_type)) if chunk_type == "IDAT"
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let diagnostics = png.validate();
+        // The sample hides its message in a private critical chunk.
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].chunk_index, Some(5));
+
+        let options = ParseOptions::default().crc_policy(CrcPolicy::Warn);
+        let png = Png::parse(&corrupted_png_bytes(), &options).unwrap();
+        let diagnostics = png.validate();
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.chunk_index == Some(1) && d.message.contains("CRC")));
+    }
+
+    #[test]
+    fn test_remove_all_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("TeSt", "one").unwrap());
+        png.append_chunk(chunk_from_strings("TeSt", "two").unwrap());
+
+        let removed = png.remove_all_chunks("TeSt").unwrap();
+        assert_eq!(removed.len(), 2);
+        assert_eq!(&removed[1].data_as_string().unwrap(), "two");
+        assert!(png.chunk_by_type("TeSt").is_none());
+        assert_eq!(png.chunks().len(), 3);
+
+        assert!(matches!(
+            png.remove_all_chunks("TeSt"),
+            Err(PngError::ChunkNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -249,6 +672,24 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_write_to() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let mut written = Vec::new();
+        png.write_to(&mut written).unwrap();
+        assert_eq!(written, PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_write_to_keeps_stored_crc() {
+        let options = ParseOptions::default().crc_policy(CrcPolicy::Warn);
+        let png = Png::parse(&corrupted_png_bytes(), &options).unwrap();
+        let mut written = Vec::new();
+        png.write_to(&mut written).unwrap();
+        assert_eq!(written, png.as_bytes());
+        assert_eq!(written, corrupted_png_bytes());
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<u8> = testing_chunks()
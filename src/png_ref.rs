@@ -0,0 +1,122 @@
+use crate::chunk_ref::ChunkRef;
+use crate::error::{PngError, Result};
+use crate::png::{ParseOptions, Png};
+
+/// A PNG whose chunks borrow from the input buffer (or a memory map), for
+/// tools that only list or search chunks. Only the chunk index is allocated.
+#[derive(Debug, Clone)]
+pub struct PngRef<'a> {
+    chunks: Vec<ChunkRef<'a>>,
+}
+
+impl<'a> PngRef<'a> {
+    pub fn chunks(&self) -> &[ChunkRef<'a>] {
+        &self.chunks
+    }
+
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&ChunkRef<'a>> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
+    /// Checks every stored CRC, failing on the first mismatch.
+    pub fn verify_crcs(&self) -> Result<()> {
+        self.chunks.iter().try_for_each(|chunk| chunk.verify_crc())
+    }
+
+    /// Copies every chunk into an owned `Png`, handling bad CRCs according to `options`.
+    pub fn to_owned_with(&self, options: &ParseOptions) -> Result<Png> {
+        Png::from_chunk_refs(&self.chunks, options)
+    }
+
+    /// Copies every chunk into an owned `Png`, failing on any bad CRC.
+    pub fn to_owned(&self) -> Result<Png> {
+        self.to_owned_with(&ParseOptions::default())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for PngRef<'a> {
+    type Error = PngError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self> {
+        let header = &Png::STANDARD_HEADER;
+        if value.len() < header.len() || value[..header.len()] != header[..] {
+            let end = value.len().min(header.len());
+            return Err(PngError::InvalidSignature(value[..end].to_vec()));
+        }
+
+        let mut chunks = Vec::new();
+        let mut offset = header.len();
+        while offset < value.len() {
+            let (chunk, next) = ChunkRef::read_at(value, offset, chunks.len())?;
+            chunks.push(chunk);
+            offset = next;
+        }
+        Ok(PngRef { chunks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::png::CrcPolicy;
+    use std::str::FromStr;
+
+    fn testing_bytes() -> Vec<u8> {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("FrSt").unwrap(), b"first".to_vec()),
+            Chunk::new(ChunkType::from_str("miDl").unwrap(), b"middle".to_vec()),
+            Chunk::new(ChunkType::from_str("LASt").unwrap(), b"last".to_vec()),
+        ])
+        .as_bytes()
+    }
+
+    #[test]
+    fn test_png_ref_chunks() {
+        let bytes = testing_bytes();
+        let png = PngRef::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(png.chunks().len(), 3);
+        let chunk = png.chunk_by_type("miDl").unwrap();
+        assert_eq!(chunk.data(), b"middle");
+        assert!(png.verify_crcs().is_ok());
+    }
+
+    #[test]
+    fn test_png_ref_to_owned() {
+        let bytes = testing_bytes();
+        let png = PngRef::try_from(bytes.as_slice())
+            .unwrap()
+            .to_owned()
+            .unwrap();
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_png_ref_bad_crc() {
+        let mut bytes = testing_bytes();
+        bytes[8 + 8] ^= 1;
+        let png = PngRef::try_from(bytes.as_slice()).unwrap();
+
+        assert!(png.verify_crcs().is_err());
+        assert!(png.to_owned().is_err());
+        let options = ParseOptions::default().crc_policy(CrcPolicy::Recompute);
+        assert!(png.to_owned_with(&options).is_ok());
+    }
+
+    #[test]
+    fn test_png_ref_errors() {
+        let bytes = testing_bytes();
+        assert!(matches!(
+            PngRef::try_from(&bytes[..4]),
+            Err(PngError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            PngRef::try_from(&bytes[..bytes.len() - 1]),
+            Err(PngError::TruncatedChunk { chunk_index: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,304 @@
+use std::io::{self, Read};
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::png::{ParseOptions, ParseWarning, Png};
+
+/// Reads a PNG one chunk at a time from any `Read`, so only the current
+/// chunk is ever held in memory.
+///
+/// The signature is checked on the first call to `next`. After the first
+/// error the iterator is exhausted.
+pub struct ChunkReader<R: Read> {
+    reader: R,
+    options: ParseOptions,
+    skip: Vec<ChunkType>,
+    signature_checked: bool,
+    done: bool,
+    chunk_index: usize,
+    offset: usize,
+    warnings: Vec<ParseWarning>,
+}
+
+impl<R: Read> ChunkReader<R> {
+    pub fn new(reader: R) -> ChunkReader<R> {
+        ChunkReader::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> ChunkReader<R> {
+        ChunkReader {
+            reader,
+            options,
+            skip: Vec::new(),
+            signature_checked: false,
+            done: false,
+            chunk_index: 0,
+            offset: 0,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Chunks of these types are stepped over without loading their data
+    /// and are not yielded. Their CRCs are not checked.
+    pub fn skip_types(mut self, types: impl IntoIterator<Item = ChunkType>) -> ChunkReader<R> {
+        self.skip.extend(types);
+        self
+    }
+
+    /// Byte offset of the next chunk from the start of the file.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Problems tolerated so far, such as CRC mismatches under `CrcPolicy::Warn`.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads until `buf` is full or the input ends, returning how many bytes were read.
+    fn fill(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(filled)
+    }
+
+    fn truncated(&self, needed: usize, available: usize) -> PngError {
+        PngError::TruncatedChunk {
+            chunk_index: self.chunk_index,
+            offset: self.offset,
+            needed,
+            available,
+        }
+    }
+
+    fn check_signature(&mut self) -> Result<()> {
+        let mut signature = [0; 8];
+        let read = self.fill(&mut signature)?;
+        if signature[..read] != Png::STANDARD_HEADER {
+            return Err(PngError::InvalidSignature(signature[..read].to_vec()));
+        }
+        self.offset = Png::STANDARD_HEADER.len();
+        Ok(())
+    }
+
+    /// Reads the next chunk, or returns `None` at a clean end of input.
+    fn read_chunk(&mut self) -> Result<Option<Chunk>> {
+        loop {
+            let mut header = [0; 8];
+            let read = self.fill(&mut header)?;
+            if read == 0 {
+                return Ok(None);
+            }
+            if read < header.len() {
+                return Err(self.truncated(12, read));
+            }
+
+            let length = u32::from_be_bytes(header[..4].try_into().unwrap());
+            if length > i32::MAX as u32 {
+                return Err(PngError::LengthOverflow {
+                    chunk_index: self.chunk_index,
+                    offset: self.offset,
+                    length,
+                    remaining: None,
+                });
+            }
+            let chunk_type = ChunkType::try_from(<[u8; 4]>::try_from(&header[4..]).unwrap())?;
+            let needed = 12 + length as usize;
+
+            if self.skip.contains(&chunk_type) {
+                let rest = length as u64 + 4;
+                let skipped = io::copy(&mut (&mut self.reader).take(rest), &mut io::sink())?;
+                if skipped < rest {
+                    return Err(self.truncated(needed, 8 + skipped as usize));
+                }
+                self.chunk_index += 1;
+                self.offset += needed;
+                continue;
+            }
+
+            // `take` grows the buffer as data arrives instead of trusting the
+            // declared length up front.
+            let mut data = Vec::new();
+            (&mut self.reader)
+                .take(length as u64)
+                .read_to_end(&mut data)?;
+            let mut crc = [0; 4];
+            let crc_read = if data.len() == length as usize {
+                self.fill(&mut crc)?
+            } else {
+                0
+            };
+            if crc_read < crc.len() {
+                return Err(self.truncated(needed, 8 + data.len() + crc_read));
+            }
+
+            let chunk = self.options.build_chunk(
+                self.chunk_index,
+                chunk_type,
+                data,
+                u32::from_be_bytes(crc),
+                &mut self.warnings,
+            )?;
+            self.chunk_index += 1;
+            self.offset += needed;
+            return Ok(Some(chunk));
+        }
+    }
+}
+
+impl<R: Read> Iterator for ChunkReader<R> {
+    type Item = Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if !self.signature_checked {
+            self.signature_checked = true;
+            if let Err(e) = self.check_signature() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+
+        match self.read_chunk() {
+            Ok(Some(chunk)) => Some(Ok(chunk)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::CrcPolicy;
+    use std::str::FromStr;
+
+    fn testing_png_bytes() -> Vec<u8> {
+        let chunks = vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![7; 100]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hidden".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ];
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    fn chunk_types(chunks: &[Chunk]) -> Vec<String> {
+        chunks
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_reads_every_chunk() {
+        let bytes = testing_png_bytes();
+        let chunks: Vec<Chunk> = ChunkReader::new(bytes.as_slice())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(chunk_types(&chunks), ["IHDR", "IDAT", "ruSt", "IEND"]);
+        assert_eq!(chunks[1].data(), &[7; 100][..]);
+    }
+
+    #[test]
+    fn test_matches_png_parse() {
+        let bytes = testing_png_bytes();
+        let png = Png::try_from(bytes.as_slice()).unwrap();
+        let chunks: Vec<Chunk> = ChunkReader::new(bytes.as_slice())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(Png::from_chunks(chunks).as_bytes(), png.as_bytes());
+    }
+
+    #[test]
+    fn test_skip_types() {
+        let bytes = testing_png_bytes();
+        let mut reader =
+            ChunkReader::new(bytes.as_slice()).skip_types([ChunkType::from_str("IDAT").unwrap()]);
+        let chunks: Vec<Chunk> = reader.by_ref().collect::<Result<_>>().unwrap();
+        assert_eq!(chunk_types(&chunks), ["IHDR", "ruSt", "IEND"]);
+        assert_eq!(reader.offset(), bytes.len());
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        let mut bytes = testing_png_bytes();
+        bytes[0] = 0;
+        let mut reader = ChunkReader::new(bytes.as_slice());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(PngError::InvalidSignature(_)))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_truncated_stream() {
+        let bytes = testing_png_bytes();
+        for end in 9..bytes.len() {
+            let result: Result<Vec<Chunk>> = ChunkReader::new(&bytes[..end]).collect();
+            // Cutting exactly between chunks is a clean end of input.
+            if let Err(e) = result {
+                assert!(matches!(e, PngError::TruncatedChunk { .. }), "{:?}", e);
+            }
+        }
+
+        let result: Result<Vec<Chunk>> = ChunkReader::new(&bytes[..8 + 25 + 50]).collect();
+        assert!(matches!(
+            result,
+            Err(PngError::TruncatedChunk {
+                chunk_index: 1,
+                offset: 33,
+                needed: 112,
+                available: 50,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_crc_policy() {
+        let mut bytes = testing_png_bytes();
+        // Corrupt the IDAT data.
+        bytes[8 + 25 + 8] ^= 1;
+
+        let result: Result<Vec<Chunk>> = ChunkReader::new(bytes.as_slice()).collect();
+        assert!(matches!(result, Err(PngError::CrcMismatch { .. })));
+
+        let options = ParseOptions::default().crc_policy(CrcPolicy::Warn);
+        let mut reader = ChunkReader::with_options(bytes.as_slice(), options);
+        let chunks: Vec<Chunk> = reader.by_ref().collect::<Result<_>>().unwrap();
+        assert!(!chunks[1].crc_is_valid());
+        assert_eq!(reader.warnings().len(), 1);
+        assert_eq!(reader.warnings()[0].chunk_index, 1);
+        assert!(matches!(
+            reader.warnings()[0].error,
+            PngError::CrcMismatch { .. }
+        ));
+
+        let options = ParseOptions::default().crc_policy(CrcPolicy::Recompute);
+        let mut reader = ChunkReader::with_options(bytes.as_slice(), options);
+        let chunks: Vec<Chunk> = reader.by_ref().collect::<Result<_>>().unwrap();
+        assert!(chunks.iter().all(|chunk| chunk.crc_is_valid()));
+        assert!(reader.warnings().is_empty());
+    }
+}
@@ -0,0 +1,731 @@
+use std::fmt;
+use std::str::FromStr;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::chunk::Chunk;
+use crate::chunk_data::{check_type, invalid};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::png::Png;
+use crate::validate::Diagnostic;
+use crate::zlib;
+
+/// Longest keyword the PNG spec allows, in bytes.
+pub const MAX_KEYWORD_LENGTH: usize = 79;
+
+/// A `tEXt` chunk: an uncompressed Latin-1 keyword and text pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    pub keyword: String,
+    pub text: String,
+}
+
+/// Checks `keyword` against the spec: 1 to 79 printable Latin-1 characters,
+/// with no leading, trailing or consecutive spaces.
+pub(crate) fn check_keyword(chunk_type: ChunkType, keyword: &str) -> Result<()> {
+    let length = keyword.chars().count();
+    if length == 0 || length > MAX_KEYWORD_LENGTH {
+        return Err(invalid(
+            chunk_type,
+            format!(
+                "keyword must be 1 to {} bytes, got {}",
+                MAX_KEYWORD_LENGTH, length
+            ),
+        ));
+    }
+    if let Some(c) = keyword
+        .chars()
+        .find(|&c| !matches!(c as u32, 32..=126 | 161..=255))
+    {
+        return Err(invalid(
+            chunk_type,
+            format!("keyword contains {:?}, which is not printable Latin-1", c),
+        ));
+    }
+    if keyword.starts_with(' ') || keyword.ends_with(' ') || keyword.contains("  ") {
+        return Err(invalid(
+            chunk_type,
+            format!(
+                "keyword {:?} has leading, trailing or repeated spaces",
+                keyword
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Encodes `text` as Latin-1, rejecting characters outside it and nulls.
+pub(crate) fn encode_latin1(chunk_type: ChunkType, text: &str) -> Result<Vec<u8>> {
+    text.chars()
+        .map(|c| match c as u32 {
+            1..=255 => Ok(c as u8),
+            _ => Err(invalid(
+                chunk_type,
+                format!("{:?} cannot be stored as Latin-1", c),
+            )),
+        })
+        .collect()
+}
+
+/// Every Latin-1 byte is the Unicode code point of the same value.
+pub(crate) fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+impl TextChunk {
+    /// Builds a text entry, checking the keyword and that both fields are Latin-1.
+    pub fn new(keyword: &str, text: &str) -> Result<TextChunk> {
+        let entry = TextChunk {
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+        };
+        entry.validate()?;
+        Ok(entry)
+    }
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("tEXt").unwrap()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        check_keyword(TextChunk::chunk_type(), &self.keyword)?;
+        encode_latin1(TextChunk::chunk_type(), &self.text)?;
+        Ok(())
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.validate()?;
+        let mut data = encode_latin1(TextChunk::chunk_type(), &self.keyword)?;
+        data.push(0);
+        data.extend(encode_latin1(TextChunk::chunk_type(), &self.text)?);
+        Ok(Chunk::new(TextChunk::chunk_type(), data))
+    }
+}
+
+impl TryFrom<&Chunk> for TextChunk {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, TextChunk::chunk_type())?;
+        let data = chunk.data();
+        let separator = data.iter().position(|&b| b == 0).ok_or_else(|| {
+            invalid(
+                TextChunk::chunk_type(),
+                "missing null separator after keyword".to_string(),
+            )
+        })?;
+        let entry = TextChunk {
+            keyword: decode_latin1(&data[..separator]),
+            text: decode_latin1(&data[separator + 1..]),
+        };
+        entry.validate()?;
+        Ok(entry)
+    }
+}
+
+impl fmt::Display for TextChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.keyword, self.text)
+    }
+}
+
+/// A `zTXt` chunk: a Latin-1 keyword and text, with the text stored zlib compressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedTextChunk {
+    pub keyword: String,
+    pub text: String,
+}
+
+impl CompressedTextChunk {
+    /// Builds a compressed text entry, checking the keyword and that both fields are Latin-1.
+    pub fn new(keyword: &str, text: &str) -> Result<CompressedTextChunk> {
+        let entry = CompressedTextChunk {
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+        };
+        entry.validate()?;
+        Ok(entry)
+    }
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("zTXt").unwrap()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        check_keyword(CompressedTextChunk::chunk_type(), &self.keyword)?;
+        encode_latin1(CompressedTextChunk::chunk_type(), &self.text)?;
+        Ok(())
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.validate()?;
+        let chunk_type = CompressedTextChunk::chunk_type();
+        let mut data = encode_latin1(chunk_type, &self.keyword)?;
+        data.push(0);
+        data.push(zlib::COMPRESSION_METHOD_ZLIB);
+        data.extend(zlib::compress(&encode_latin1(chunk_type, &self.text)?));
+        Ok(Chunk::new(chunk_type, data))
+    }
+}
+
+impl TryFrom<&Chunk> for CompressedTextChunk {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let chunk_type = CompressedTextChunk::chunk_type();
+        check_type(chunk, chunk_type)?;
+        let data = chunk.data();
+        let separator = data.iter().position(|&b| b == 0).ok_or_else(|| {
+            invalid(
+                chunk_type,
+                "missing null separator after keyword".to_string(),
+            )
+        })?;
+        let method = *data
+            .get(separator + 1)
+            .ok_or_else(|| invalid(chunk_type, "missing compression method".to_string()))?;
+        zlib::check_method(chunk_type, method)?;
+
+        let entry = CompressedTextChunk {
+            keyword: decode_latin1(&data[..separator]),
+            text: decode_latin1(&zlib::decompress(chunk_type, &data[separator + 2..])?),
+        };
+        entry.validate()?;
+        Ok(entry)
+    }
+}
+
+impl fmt::Display for CompressedTextChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.keyword, self.text)
+    }
+}
+
+/// An `iTXt` chunk: a Latin-1 keyword with UTF-8 text, optionally compressed,
+/// plus the language of the text and the keyword translated into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InternationalTextChunk {
+    pub keyword: String,
+    pub compressed: bool,
+    /// RFC 3066 language tag such as `en-GB`. Empty means unspecified.
+    pub language_tag: String,
+    pub translated_keyword: String,
+    pub text: String,
+}
+
+fn nfc(s: &str) -> String {
+    s.nfc().collect()
+}
+
+impl InternationalTextChunk {
+    /// Builds an uncompressed entry with no language information.
+    pub fn new(keyword: &str, text: &str) -> Result<InternationalTextChunk> {
+        let entry = InternationalTextChunk {
+            keyword: keyword.to_string(),
+            compressed: false,
+            language_tag: String::new(),
+            translated_keyword: String::new(),
+            text: text.to_string(),
+        };
+        entry.validate()?;
+        Ok(entry)
+    }
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("iTXt").unwrap()
+    }
+
+    /// Checks the keyword rules and that the language tag is made of ASCII
+    /// letters, digits and hyphens. The keyword is checked after NFC
+    /// normalisation, as that is what gets written.
+    pub fn validate(&self) -> Result<()> {
+        let chunk_type = InternationalTextChunk::chunk_type();
+        check_keyword(chunk_type, &nfc(&self.keyword))?;
+        if !self
+            .language_tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(invalid(
+                chunk_type,
+                format!("invalid language tag {:?}", self.language_tag),
+            ));
+        }
+        if self.translated_keyword.contains('\0') {
+            return Err(invalid(
+                chunk_type,
+                "translated keyword contains a null byte".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Encodes the chunk, NFC normalising the keywords and text first.
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.validate()?;
+        let chunk_type = InternationalTextChunk::chunk_type();
+        let mut data = encode_latin1(chunk_type, &nfc(&self.keyword))?;
+        data.push(0);
+        data.push(self.compressed as u8);
+        data.push(zlib::COMPRESSION_METHOD_ZLIB);
+        data.extend_from_slice(self.language_tag.as_bytes());
+        data.push(0);
+        data.extend_from_slice(nfc(&self.translated_keyword).as_bytes());
+        data.push(0);
+        let text = nfc(&self.text);
+        if self.compressed {
+            data.extend(zlib::compress(text.as_bytes()));
+        } else {
+            data.extend_from_slice(text.as_bytes());
+        }
+        Ok(Chunk::new(chunk_type, data))
+    }
+}
+
+/// Splits `data` at the next null byte, returning the field and the rest.
+fn split_null<'a>(
+    chunk_type: ChunkType,
+    data: &'a [u8],
+    field: &str,
+) -> Result<(&'a [u8], &'a [u8])> {
+    let end = data.iter().position(|&b| b == 0).ok_or_else(|| {
+        invalid(
+            chunk_type,
+            format!("missing null separator after {}", field),
+        )
+    })?;
+    Ok((&data[..end], &data[end + 1..]))
+}
+
+impl TryFrom<&Chunk> for InternationalTextChunk {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let chunk_type = InternationalTextChunk::chunk_type();
+        check_type(chunk, chunk_type)?;
+        let (keyword, rest) = split_null(chunk_type, chunk.data(), "keyword")?;
+        if rest.len() < 2 {
+            return Err(invalid(
+                chunk_type,
+                "missing compression flag and method".to_string(),
+            ));
+        }
+        let compressed = match rest[0] {
+            0 => false,
+            1 => true,
+            flag => {
+                return Err(invalid(
+                    chunk_type,
+                    format!("invalid compression flag {}", flag),
+                ))
+            }
+        };
+        // The method byte is only meaningful for compressed text.
+        if compressed {
+            zlib::check_method(chunk_type, rest[1])?;
+        }
+        let (language_tag, rest) = split_null(chunk_type, &rest[2..], "language tag")?;
+        let (translated_keyword, text) = split_null(chunk_type, rest, "translated keyword")?;
+        let text = if compressed {
+            zlib::decompress(chunk_type, text)?
+        } else {
+            text.to_vec()
+        };
+
+        let entry = InternationalTextChunk {
+            keyword: decode_latin1(keyword),
+            compressed,
+            language_tag: decode_latin1(language_tag),
+            translated_keyword: String::from_utf8(translated_keyword.to_vec())?,
+            text: String::from_utf8(text)?,
+        };
+        entry.validate()?;
+        Ok(entry)
+    }
+}
+
+impl fmt::Display for InternationalTextChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        if !self.language_tag.is_empty() || !self.translated_keyword.is_empty() {
+            write!(f, " [{}] {}", self.language_tag, self.translated_keyword)?;
+        }
+        write!(f, ": {}", self.text)
+    }
+}
+
+/// Any of the textual metadata chunks, decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextEntry {
+    Text(TextChunk),
+    Compressed(CompressedTextChunk),
+    International(InternationalTextChunk),
+}
+
+impl TextEntry {
+    /// Whether chunks of this type hold text metadata.
+    pub fn is_text_type(chunk_type: ChunkType) -> bool {
+        chunk_type == TextChunk::chunk_type()
+            || chunk_type == CompressedTextChunk::chunk_type()
+            || chunk_type == InternationalTextChunk::chunk_type()
+    }
+
+    pub fn keyword(&self) -> &str {
+        match self {
+            TextEntry::Text(entry) => &entry.keyword,
+            TextEntry::Compressed(entry) => &entry.keyword,
+            TextEntry::International(entry) => &entry.keyword,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            TextEntry::Text(entry) => &entry.text,
+            TextEntry::Compressed(entry) => &entry.text,
+            TextEntry::International(entry) => &entry.text,
+        }
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        match self {
+            TextEntry::Text(entry) => entry.to_chunk(),
+            TextEntry::Compressed(entry) => entry.to_chunk(),
+            TextEntry::International(entry) => entry.to_chunk(),
+        }
+    }
+}
+
+impl From<TextChunk> for TextEntry {
+    fn from(entry: TextChunk) -> Self {
+        TextEntry::Text(entry)
+    }
+}
+
+impl From<CompressedTextChunk> for TextEntry {
+    fn from(entry: CompressedTextChunk) -> Self {
+        TextEntry::Compressed(entry)
+    }
+}
+
+impl From<InternationalTextChunk> for TextEntry {
+    fn from(entry: InternationalTextChunk) -> Self {
+        TextEntry::International(entry)
+    }
+}
+
+impl TryFrom<&Chunk> for TextEntry {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        let chunk_type = chunk.chunk_type();
+        if chunk_type == TextChunk::chunk_type() {
+            TextChunk::try_from(chunk).map(TextEntry::Text)
+        } else if chunk_type == CompressedTextChunk::chunk_type() {
+            CompressedTextChunk::try_from(chunk).map(TextEntry::Compressed)
+        } else if chunk_type == InternationalTextChunk::chunk_type() {
+            InternationalTextChunk::try_from(chunk).map(TextEntry::International)
+        } else {
+            Err(PngError::ChunkNotFound("tEXt".to_string()))
+        }
+    }
+}
+
+impl fmt::Display for TextEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextEntry::Text(entry) => write!(f, "{}", entry),
+            TextEntry::Compressed(entry) => write!(f, "{} (compressed)", entry),
+            TextEntry::International(entry) if entry.compressed => {
+                write!(f, "{} (compressed)", entry)
+            }
+            TextEntry::International(entry) => write!(f, "{}", entry),
+        }
+    }
+}
+
+/// Reads just the keyword, so matching does not inflate compressed text and
+/// an entry whose text is corrupt can still be found and removed.
+fn keyword_of(chunk: &Chunk) -> Option<String> {
+    if !TextEntry::is_text_type(chunk.chunk_type()) {
+        return None;
+    }
+    let data = chunk.data();
+    let separator = data.iter().position(|&b| b == 0)?;
+    Some(decode_latin1(&data[..separator]))
+}
+
+/// Keywords are compared after NFC normalisation, as `iTXt` stores them that way.
+fn is_text_with_keyword(chunk: &Chunk, keyword: &str) -> bool {
+    keyword_of(chunk).is_some_and(|found| nfc(&found) == nfc(keyword))
+}
+
+impl Png {
+    /// Decodes every `tEXt`, `zTXt` and `iTXt` chunk in file order. A chunk that
+    /// fails to decode is left out and reported as a diagnostic instead.
+    pub fn text_entries(&self) -> (Vec<TextEntry>, Vec<Diagnostic>) {
+        let mut entries = Vec::new();
+        let mut diagnostics = Vec::new();
+        for (index, chunk) in self.chunks().iter().enumerate() {
+            if !TextEntry::is_text_type(chunk.chunk_type()) {
+                continue;
+            }
+            match TextEntry::try_from(chunk) {
+                Ok(entry) => entries.push(entry),
+                Err(error) => diagnostics.push(Diagnostic::error(Some(index), error.to_string())),
+            }
+        }
+        (entries, diagnostics)
+    }
+
+    /// The first text entry with this keyword. Keywords are case sensitive.
+    pub fn text(&self, keyword: &str) -> Result<Option<TextEntry>> {
+        self.chunks()
+            .iter()
+            .find(|chunk| is_text_with_keyword(chunk, keyword))
+            .map(TextEntry::try_from)
+            .transpose()
+    }
+
+    /// Replaces any text entries with the same keyword by `entry`.
+    pub fn set_text(&mut self, entry: &TextEntry) -> Result<()> {
+        let chunk = entry.to_chunk()?;
+        self.remove_chunks_where(|chunk| is_text_with_keyword(chunk, entry.keyword()));
+        self.append_chunk(chunk);
+        Ok(())
+    }
+
+    /// Removes every text entry with this keyword, returning the raw chunks in
+    /// file order. Entries are matched on their keyword alone, so a corrupt one
+    /// can still be removed.
+    pub fn remove_text(&mut self, keyword: &str) -> Result<Vec<Chunk>> {
+        let removed = self.remove_chunks_where(|chunk| is_text_with_keyword(chunk, keyword));
+        if removed.is_empty() {
+            return Err(PngError::ChunkNotFound(format!(
+                "text keyword {:?}",
+                keyword
+            )));
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_png() -> Png {
+        let mut png = Png::from_chunks(vec![Chunk::new(
+            ChunkType::from_str("IEND").unwrap(),
+            Vec::new(),
+        )]);
+        png.append_chunk(TextChunk::new("Title", "Dice").unwrap().to_chunk().unwrap());
+        png.append_chunk(
+            TextChunk::new("Author", "Zoë Müller")
+                .unwrap()
+                .to_chunk()
+                .unwrap(),
+        );
+        png
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let entry = TextChunk::new("Comment", "Café ©").unwrap();
+        let chunk = entry.to_chunk().unwrap();
+        assert_eq!(chunk.data(), b"Comment\0Caf\xe9 \xa9");
+        assert_eq!(TextChunk::try_from(&chunk).unwrap(), entry);
+    }
+
+    #[test]
+    fn test_keyword_rules() {
+        assert!(TextChunk::new("", "text").is_err());
+        assert!(TextChunk::new(&"k".repeat(79), "text").is_ok());
+        assert!(TextChunk::new(&"k".repeat(80), "text").is_err());
+        assert!(TextChunk::new(" Title", "text").is_err());
+        assert!(TextChunk::new("Title ", "text").is_err());
+        assert!(TextChunk::new("Two  spaces", "text").is_err());
+        assert!(TextChunk::new("Tab\tkey", "text").is_err());
+        assert!(TextChunk::new("Ünïcödé", "text").is_ok());
+        assert!(TextChunk::new("Emoji 🎲", "text").is_err());
+    }
+
+    #[test]
+    fn test_text_must_be_latin1() {
+        assert!(TextChunk::new("Title", "日本").is_err());
+        assert!(TextChunk::new("Title", "nul\0byte").is_err());
+    }
+
+    #[test]
+    fn test_missing_separator() {
+        let chunk = Chunk::new(TextChunk::chunk_type(), b"no separator".to_vec());
+        assert!(matches!(
+            TextChunk::try_from(&chunk),
+            Err(PngError::InvalidChunkData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_compressed_text_round_trip() {
+        let entry = CompressedTextChunk::new("Comment", &"Café ".repeat(100)).unwrap();
+        let chunk = entry.to_chunk().unwrap();
+        assert_eq!(&chunk.data()[..9], b"Comment\0\0");
+        assert!(chunk.data().len() < 100);
+        assert_eq!(CompressedTextChunk::try_from(&chunk).unwrap(), entry);
+        assert_eq!(
+            TextEntry::try_from(&chunk).unwrap(),
+            TextEntry::Compressed(entry)
+        );
+    }
+
+    #[test]
+    fn test_compressed_text_errors() {
+        let chunk_type = CompressedTextChunk::chunk_type();
+        let mut data = b"Comment\0".to_vec();
+        data.push(1);
+        data.extend(zlib::compress(b"text"));
+        assert!(matches!(
+            CompressedTextChunk::try_from(&Chunk::new(chunk_type, data)),
+            Err(PngError::UnknownCompressionMethod { method: 1, .. })
+        ));
+
+        let chunk = Chunk::new(chunk_type, b"Comment\0\0garbage".to_vec());
+        assert!(matches!(
+            CompressedTextChunk::try_from(&chunk),
+            Err(PngError::InvalidChunkData { .. })
+        ));
+
+        let chunk = Chunk::new(chunk_type, b"Comment\0".to_vec());
+        assert!(CompressedTextChunk::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_international_text_round_trip() {
+        for compressed in [false, true] {
+            let entry = InternationalTextChunk {
+                keyword: "Title".to_string(),
+                compressed,
+                language_tag: "ja".to_string(),
+                translated_keyword: "タイトル".to_string(),
+                text: "サイコロ 🎲".to_string(),
+            };
+            let chunk = entry.to_chunk().unwrap();
+            assert_eq!(InternationalTextChunk::try_from(&chunk).unwrap(), entry);
+            assert_eq!(
+                TextEntry::try_from(&chunk).unwrap(),
+                TextEntry::International(entry)
+            );
+        }
+    }
+
+    #[test]
+    fn test_international_text_layout() {
+        let chunk = InternationalTextChunk::new("Title", "Dice")
+            .unwrap()
+            .to_chunk()
+            .unwrap();
+        assert_eq!(chunk.data(), b"Title\0\0\0\0\0Dice");
+    }
+
+    #[test]
+    fn test_international_text_is_nfc_normalised() {
+        // "e" followed by a combining acute accent.
+        let entry = InternationalTextChunk::new("Cafe\u{301}", "Cafe\u{301}").unwrap();
+        let decoded = InternationalTextChunk::try_from(&entry.to_chunk().unwrap()).unwrap();
+        assert_eq!(decoded.keyword, "Café");
+        assert_eq!(decoded.text, "Café");
+    }
+
+    #[test]
+    fn test_international_text_errors() {
+        let chunk_type = InternationalTextChunk::chunk_type();
+        let chunk = Chunk::new(chunk_type, b"Title\0\x02\0\0\0text".to_vec());
+        assert!(matches!(
+            InternationalTextChunk::try_from(&chunk),
+            Err(PngError::InvalidChunkData { .. })
+        ));
+
+        let chunk = Chunk::new(chunk_type, b"Title\0\x01\x05\0\0text".to_vec());
+        assert!(matches!(
+            InternationalTextChunk::try_from(&chunk),
+            Err(PngError::UnknownCompressionMethod { method: 5, .. })
+        ));
+
+        let chunk = Chunk::new(chunk_type, b"Title\0\0\0\0\0\xff".to_vec());
+        assert!(matches!(
+            InternationalTextChunk::try_from(&chunk),
+            Err(PngError::InvalidUtf8(_))
+        ));
+
+        let chunk = Chunk::new(chunk_type, b"Title\0\0\0en".to_vec());
+        assert!(InternationalTextChunk::try_from(&chunk).is_err());
+
+        let mut entry = InternationalTextChunk::new("Title", "text").unwrap();
+        entry.language_tag = "en GB".to_string();
+        assert!(entry.to_chunk().is_err());
+    }
+
+    #[test]
+    fn test_text_entries() {
+        let png = testing_png();
+        let (entries, diagnostics) = png.text_entries();
+        assert_eq!(entries.len(), 2);
+        assert!(diagnostics.is_empty());
+        assert_eq!(entries[1].text(), "Zoë Müller");
+        assert_eq!(png.text("Title").unwrap().unwrap().text(), "Dice");
+        assert!(png.text("title").unwrap().is_none());
+        // Text chunks go before IEND.
+        assert_eq!(
+            png.chunks().last().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+    }
+
+    #[test]
+    fn test_set_and_remove_text() {
+        let mut png = testing_png();
+        let entry = CompressedTextChunk::new("Title", "Two dice").unwrap();
+        png.set_text(&entry.clone().into()).unwrap();
+        assert_eq!(png.text_entries().0.len(), 2);
+        assert_eq!(
+            png.text("Title").unwrap(),
+            Some(TextEntry::Compressed(entry))
+        );
+
+        let removed = png.remove_text("Title").unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(png.text("Title").unwrap().is_none());
+        assert!(matches!(
+            png.remove_text("Title"),
+            Err(PngError::ChunkNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_text_is_listed_and_removable() {
+        let mut png = testing_png();
+        // A keyword with a leading space breaks the spec rules.
+        png.append_chunk(Chunk::new(TextChunk::chunk_type(), b" Bad\0text".to_vec()));
+        let mut broken = CompressedTextChunk::new("Broken", "text")
+            .unwrap()
+            .to_chunk()
+            .unwrap()
+            .data()
+            .to_vec();
+        broken.truncate(broken.len() - 4);
+        png.append_chunk(Chunk::new(CompressedTextChunk::chunk_type(), broken));
+
+        let (entries, diagnostics) = png.text_entries();
+        assert_eq!(entries.len(), 2);
+        let indices: Vec<Option<usize>> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.chunk_index)
+            .collect();
+        assert_eq!(indices, [Some(2), Some(3)]);
+
+        let removed = png.remove_text(" Bad").unwrap();
+        assert_eq!(removed[0].data(), b" Bad\0text");
+        assert_eq!(png.remove_text("Broken").unwrap().len(), 1);
+        assert!(png.text_entries().1.is_empty());
+    }
+}
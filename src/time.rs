@@ -0,0 +1,256 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::chunk::Chunk;
+use crate::chunk_data::{check_type, expect_length, invalid, read_u16};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::png::Png;
+
+/// The decoded contents of the `tIME` chunk: when the image was last changed, in UTC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    /// Up to 60 to allow for leap seconds.
+    pub second: u8,
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts days since 1970-01-01 into a (year, month, day) date, using
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl Time {
+    pub const LENGTH: usize = 7;
+
+    pub fn chunk_type() -> ChunkType {
+        ChunkType::from_str("tIME").unwrap()
+    }
+
+    /// The UTC time `seconds` after the Unix epoch.
+    pub fn from_unix_seconds(seconds: u64) -> Time {
+        let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+        let rem = seconds % 86_400;
+        Time {
+            year: year as u16,
+            month,
+            day,
+            hour: (rem / 3600) as u8,
+            minute: (rem % 3600 / 60) as u8,
+            second: (rem % 60) as u8,
+        }
+    }
+
+    /// The current UTC time.
+    pub fn now() -> Time {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        Time::from_unix_seconds(seconds)
+    }
+
+    /// Checks every field is in range, including the day for the given month.
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=12).contains(&self.month) {
+            return Err(invalid(
+                Time::chunk_type(),
+                format!("month {} is out of range", self.month),
+            ));
+        }
+        if self.day == 0 || self.day > days_in_month(self.year, self.month) {
+            return Err(invalid(
+                Time::chunk_type(),
+                format!(
+                    "day {} is out of range for {}-{:02}",
+                    self.day, self.year, self.month
+                ),
+            ));
+        }
+        if self.hour > 23 {
+            return Err(invalid(
+                Time::chunk_type(),
+                format!("hour {} is out of range", self.hour),
+            ));
+        }
+        if self.minute > 59 {
+            return Err(invalid(
+                Time::chunk_type(),
+                format!("minute {} is out of range", self.minute),
+            ));
+        }
+        if self.second > 60 {
+            return Err(invalid(
+                Time::chunk_type(),
+                format!("second {} is out of range", self.second),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        self.validate()?;
+        let mut data = Vec::with_capacity(Time::LENGTH);
+        data.extend_from_slice(&self.year.to_be_bytes());
+        data.extend_from_slice(&[self.month, self.day, self.hour, self.minute, self.second]);
+        Ok(Chunk::new(Time::chunk_type(), data))
+    }
+}
+
+impl TryFrom<&Chunk> for Time {
+    type Error = PngError;
+
+    fn try_from(chunk: &Chunk) -> Result<Self> {
+        check_type(chunk, Time::chunk_type())?;
+        expect_length(chunk, Time::LENGTH)?;
+        let data = chunk.data();
+        let time = Time {
+            year: read_u16(data, 0),
+            month: data[2],
+            day: data[3],
+            hour: data[4],
+            minute: data[5],
+            second: data[6],
+        };
+        time.validate()?;
+        Ok(time)
+    }
+}
+
+impl fmt::Display for Time {
+    /// ISO 8601 in UTC, e.g. `2024-03-01T12:30:00Z`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+impl Png {
+    /// Decodes the `tIME` chunk, if there is one.
+    pub fn last_modified(&self) -> Result<Option<Time>> {
+        self.chunk_by_type("tIME").map(Time::try_from).transpose()
+    }
+
+    /// Replaces the `tIME` chunk with `time`, adding one if missing.
+    pub fn set_last_modified(&mut self, time: Time) -> Result<()> {
+        let chunk = time.to_chunk()?;
+        self.remove_chunks_where(|chunk| chunk.chunk_type() == Time::chunk_type());
+        self.append_chunk(chunk);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_time() -> Time {
+        Time {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 23,
+            minute: 59,
+            second: 60,
+        }
+    }
+
+    #[test]
+    fn test_time_round_trip() {
+        let time = testing_time();
+        let chunk = time.to_chunk().unwrap();
+        assert_eq!(chunk.data(), [0x07, 0xe8, 2, 29, 23, 59, 60]);
+        assert_eq!(Time::try_from(&chunk).unwrap(), time);
+        assert_eq!(time.to_string(), "2024-02-29T23:59:60Z");
+    }
+
+    #[test]
+    fn test_time_validation() {
+        let mut time = testing_time();
+        time.year = 2023;
+        assert!(time.validate().is_err());
+        time.year = 1900;
+        assert!(time.validate().is_err());
+        time.year = 2000;
+        assert!(time.validate().is_ok());
+
+        for (field, value) in [(0, 13), (0, 0), (1, 0), (2, 24), (3, 60), (4, 61)] {
+            let mut time = testing_time();
+            match field {
+                0 => time.month = value,
+                1 => time.day = value,
+                2 => time.hour = value,
+                3 => time.minute = value,
+                _ => time.second = value,
+            }
+            assert!(time.to_chunk().is_err(), "{:?}", time);
+        }
+
+        let chunk = Chunk::new(Time::chunk_type(), vec![0; 6]);
+        assert!(Time::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_from_unix_seconds() {
+        assert_eq!(
+            Time::from_unix_seconds(0).to_string(),
+            "1970-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            Time::from_unix_seconds(951_782_400).to_string(),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            Time::from_unix_seconds(1_709_250_645).to_string(),
+            "2024-02-29T23:50:45Z"
+        );
+        assert!(Time::now().validate().is_ok());
+    }
+
+    #[test]
+    fn test_last_modified() {
+        let mut png = Png::from_chunks(vec![Chunk::new(
+            ChunkType::from_str("IEND").unwrap(),
+            Vec::new(),
+        )]);
+        assert_eq!(png.last_modified().unwrap(), None);
+
+        png.set_last_modified(Time::from_unix_seconds(0)).unwrap();
+        png.set_last_modified(testing_time()).unwrap();
+        assert_eq!(png.last_modified().unwrap(), Some(testing_time()));
+        assert_eq!(png.chunks().len(), 2);
+        assert_eq!(png.chunks()[1].chunk_type().to_string(), "IEND");
+    }
+}
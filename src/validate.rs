@@ -0,0 +1,359 @@
+use std::fmt;
+
+use crate::chunk::Chunk;
+use crate::ihdr::{ColorType, Ihdr};
+
+/// Chunks the PNG spec allows at most once per image.
+const SINGLE_OCCURRENCE: [&str; 16] = [
+    "IHDR", "PLTE", "IEND", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "bKGD", "hIST", "tRNS", "pHYs",
+    "tIME", "eXIf", "oFFs", "sCAL",
+];
+
+/// Ancillary chunks that must come before both `PLTE` and the first `IDAT`.
+const BEFORE_PLTE: [&str; 5] = ["cHRM", "gAMA", "iCCP", "sBIT", "sRGB"];
+
+/// Ancillary chunks that must come after `PLTE` but before the first `IDAT`.
+const AFTER_PLTE: [&str; 3] = ["bKGD", "hIST", "tRNS"];
+
+/// Ancillary chunks that must come before the first `IDAT`.
+const BEFORE_IDAT: [&str; 5] = ["pHYs", "sPLT", "oFFs", "sCAL", "eXIf"];
+
+/// Critical chunks defined by the PNG spec.
+const KNOWN_CRITICAL: [&str; 4] = ["IHDR", "PLTE", "IDAT", "IEND"];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single problem found by `Png::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Index of the offending chunk, if the problem is tied to one.
+    pub chunk_index: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(chunk_index: Option<usize>, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            chunk_index,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(chunk_index: Option<usize>, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            chunk_index,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.chunk_index {
+            Some(index) => write!(f, "{}: chunk #{}: {}", self.severity, index, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+/// Checks the chunk ordering rules from the PNG spec.
+pub fn check_chunk_order(chunks: &[Chunk]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if chunks.is_empty() {
+        diagnostics.push(Diagnostic::error(None, "image contains no chunks"));
+        return diagnostics;
+    }
+
+    let types: Vec<String> = chunks
+        .iter()
+        .map(|chunk| chunk.chunk_type().to_string())
+        .collect();
+    let first = |name: &str| types.iter().position(|t| t == name);
+    let plte = first("PLTE");
+    let idat = first("IDAT");
+
+    // IHDR
+    if types[0] != "IHDR" {
+        diagnostics.push(Diagnostic::error(Some(0), "first chunk must be IHDR"));
+    }
+    let ihdr = match Ihdr::try_from(&chunks[0]) {
+        Ok(ihdr) => Some(ihdr),
+        Err(e) => {
+            if types[0] == "IHDR" {
+                diagnostics.push(Diagnostic::error(Some(0), e.to_string()));
+            }
+            None
+        }
+    };
+
+    // IEND
+    let iend_count = types.iter().filter(|t| *t == "IEND").count();
+    if iend_count == 0 {
+        diagnostics.push(Diagnostic::error(None, "missing IEND chunk"));
+    } else if types[types.len() - 1] != "IEND" {
+        diagnostics.push(Diagnostic::error(
+            first("IEND"),
+            "IEND must be the last chunk",
+        ));
+    }
+
+    // IDAT
+    match idat {
+        None => diagnostics.push(Diagnostic::error(None, "missing IDAT chunk")),
+        Some(start) => {
+            let end = types.iter().rposition(|t| t == "IDAT").unwrap();
+            if let Some(offset) = types[start..=end].iter().position(|t| t != "IDAT") {
+                diagnostics.push(Diagnostic::error(
+                    Some(start + offset),
+                    "IDAT chunks must be consecutive",
+                ));
+            }
+        }
+    }
+
+    // PLTE
+    if let (Some(plte), Some(idat)) = (plte, idat) {
+        if plte > idat {
+            diagnostics.push(Diagnostic::error(
+                Some(plte),
+                "PLTE must come before the first IDAT",
+            ));
+        }
+    }
+    if let Some(ihdr) = ihdr {
+        match (ihdr.color_type, plte) {
+            (ColorType::Indexed, None) => diagnostics.push(Diagnostic::error(
+                None,
+                "indexed-colour images require a PLTE chunk",
+            )),
+            (ColorType::Grayscale | ColorType::GrayscaleAlpha, Some(index)) => diagnostics.push(
+                Diagnostic::error(Some(index), "PLTE must not appear in grayscale images"),
+            ),
+            _ => {}
+        }
+    }
+
+    // Duplicates
+    for name in SINGLE_OCCURRENCE {
+        let mut positions = types
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| *t == name)
+            .map(|(index, _)| index);
+        positions.next();
+        for index in positions {
+            let severity = if name.as_bytes()[0].is_ascii_uppercase() {
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            diagnostics.push(Diagnostic {
+                severity,
+                chunk_index: Some(index),
+                message: format!("duplicate {} chunk", name),
+            });
+        }
+    }
+
+    // Ancillary chunk positions
+    for (index, name) in types.iter().enumerate() {
+        let name = name.as_str();
+        let after_idat = idat.is_some_and(|idat| index > idat);
+        if BEFORE_PLTE.contains(&name) {
+            if plte.is_some_and(|plte| index > plte) || after_idat {
+                diagnostics.push(Diagnostic::warning(
+                    Some(index),
+                    format!("{} must come before PLTE and IDAT", name),
+                ));
+            }
+        } else if AFTER_PLTE.contains(&name) {
+            if plte.is_some_and(|plte| index < plte) || after_idat {
+                diagnostics.push(Diagnostic::warning(
+                    Some(index),
+                    format!("{} must come after PLTE and before IDAT", name),
+                ));
+            }
+        } else if BEFORE_IDAT.contains(&name) && after_idat {
+            diagnostics.push(Diagnostic::warning(
+                Some(index),
+                format!("{} must come before IDAT", name),
+            ));
+        }
+    }
+    if let Some(index) = first("hIST") {
+        if plte.is_none() {
+            diagnostics.push(Diagnostic::error(Some(index), "hIST requires a PLTE chunk"));
+        }
+    }
+    if let (Some(_), Some(index)) = (first("sRGB"), first("iCCP")) {
+        diagnostics.push(Diagnostic::warning(
+            Some(index),
+            "sRGB and iCCP should not both be present",
+        ));
+    }
+
+    // Unknown critical chunks
+    for (index, chunk) in chunks.iter().enumerate() {
+        let chunk_type = chunk.chunk_type();
+        if chunk_type.is_critical() && !KNOWN_CRITICAL.contains(&types[index].as_str()) {
+            diagnostics.push(Diagnostic::error(
+                Some(index),
+                format!("unknown critical chunk {}", chunk_type),
+            ));
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.chunk_index);
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::ihdr::InterlaceMethod;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), Vec::new())
+    }
+
+    fn ihdr(color_type: ColorType) -> Chunk {
+        Ihdr {
+            width: 1,
+            height: 1,
+            bit_depth: 8,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: InterlaceMethod::None,
+        }
+        .to_chunk()
+    }
+
+    fn errors(diagnostics: &[Diagnostic]) -> Vec<&Diagnostic> {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_order() {
+        let chunks = vec![
+            ihdr(ColorType::Indexed),
+            chunk("gAMA"),
+            chunk("PLTE"),
+            chunk("tRNS"),
+            chunk("IDAT"),
+            chunk("IDAT"),
+            chunk("tEXt"),
+            chunk("IEND"),
+        ];
+        assert!(check_chunk_order(&chunks).is_empty());
+    }
+
+    #[test]
+    fn test_ihdr_must_be_first() {
+        let chunks = vec![chunk("IDAT"), ihdr(ColorType::Rgb), chunk("IEND")];
+        let diagnostics = check_chunk_order(&chunks);
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.chunk_index == Some(0) && d.severity == Severity::Error));
+    }
+
+    #[test]
+    fn test_iend_must_be_last() {
+        let chunks = vec![
+            ihdr(ColorType::Rgb),
+            chunk("IDAT"),
+            chunk("IEND"),
+            chunk("ruSt"),
+        ];
+        let diagnostics = check_chunk_order(&chunks);
+        assert_eq!(errors(&diagnostics).len(), 1);
+        assert_eq!(diagnostics[0].chunk_index, Some(2));
+    }
+
+    #[test]
+    fn test_idat_must_be_consecutive() {
+        let chunks = vec![
+            ihdr(ColorType::Rgb),
+            chunk("IDAT"),
+            chunk("tEXt"),
+            chunk("IDAT"),
+            chunk("IEND"),
+        ];
+        let diagnostics = check_chunk_order(&chunks);
+        assert_eq!(errors(&diagnostics).len(), 1);
+        assert_eq!(diagnostics[0].chunk_index, Some(2));
+    }
+
+    #[test]
+    fn test_plte_rules() {
+        let chunks = vec![
+            ihdr(ColorType::Indexed),
+            chunk("IDAT"),
+            chunk("PLTE"),
+            chunk("IEND"),
+        ];
+        assert_eq!(errors(&check_chunk_order(&chunks)).len(), 1);
+
+        let chunks = vec![ihdr(ColorType::Indexed), chunk("IDAT"), chunk("IEND")];
+        assert_eq!(errors(&check_chunk_order(&chunks)).len(), 1);
+
+        let chunks = vec![
+            ihdr(ColorType::Grayscale),
+            chunk("PLTE"),
+            chunk("IDAT"),
+            chunk("IEND"),
+        ];
+        assert_eq!(errors(&check_chunk_order(&chunks)).len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_and_misplaced_ancillary_chunks() {
+        let chunks = vec![
+            ihdr(ColorType::Rgb),
+            chunk("gAMA"),
+            chunk("gAMA"),
+            chunk("IDAT"),
+            chunk("pHYs"),
+            chunk("IEND"),
+        ];
+        let diagnostics = check_chunk_order(&chunks);
+        assert!(errors(&diagnostics).is_empty());
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].chunk_index, Some(2));
+        assert_eq!(diagnostics[1].chunk_index, Some(4));
+    }
+
+    #[test]
+    fn test_unknown_critical_chunk() {
+        let chunks = vec![
+            ihdr(ColorType::Rgb),
+            chunk("RuSt"),
+            chunk("IDAT"),
+            chunk("IEND"),
+        ];
+        let diagnostics = check_chunk_order(&chunks);
+        assert_eq!(errors(&diagnostics).len(), 1);
+        assert_eq!(diagnostics[0].chunk_index, Some(1));
+    }
+}
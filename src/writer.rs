@@ -0,0 +1,211 @@
+use std::io::{self, Write};
+
+use crc::Digest;
+
+use crate::chunk::{Chunk, PNG_CRC};
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+use crate::png::Png;
+
+/// Writes a PNG incrementally to any `Write`: the signature when created,
+/// then one chunk at a time.
+///
+/// Combined with `ChunkReader` this rewrites an image while holding only one
+/// chunk in memory:
+///
+/// ```no_run
+/// # use pngme::{reader::ChunkReader, writer::PngWriter};
+/// # fn main() -> pngme::error::Result<()> {
+/// let input = std::io::BufReader::new(std::fs::File::open("in.png")?);
+/// let output = std::io::BufWriter::new(std::fs::File::create("out.png")?);
+/// let mut writer = PngWriter::new(output)?;
+/// for chunk in ChunkReader::new(input) {
+///     writer.write_chunk(&chunk?)?;
+/// }
+/// writer.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PngWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PngWriter<W> {
+    pub fn new(mut writer: W) -> Result<PngWriter<W>> {
+        writer.write_all(&Png::STANDARD_HEADER)?;
+        Ok(PngWriter { writer })
+    }
+
+    /// Writes `chunk` exactly as it is, including its stored CRC.
+    pub fn write_chunk(&mut self, chunk: &Chunk) -> Result<()> {
+        self.writer.write_all(&chunk.length().to_be_bytes())?;
+        self.writer.write_all(&chunk.chunk_type().bytes())?;
+        self.writer.write_all(chunk.data())?;
+        self.writer.write_all(&chunk.crc().to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Writes a chunk from its type and data, computing the CRC as it goes.
+    pub fn write_data(&mut self, chunk_type: ChunkType, data: &[u8]) -> Result<()> {
+        let mut chunk = self.start_chunk(chunk_type, data.len() as u32)?;
+        chunk.write_all(data)?;
+        chunk.finish()
+    }
+
+    /// Starts a chunk whose `length` bytes of data will be written through the
+    /// returned `ChunkWriter`, so large payloads never need to be in memory at once.
+    pub fn start_chunk(
+        &mut self,
+        chunk_type: ChunkType,
+        length: u32,
+    ) -> Result<ChunkWriter<'_, W>> {
+        self.writer.write_all(&length.to_be_bytes())?;
+        self.writer.write_all(&chunk_type.bytes())?;
+        let mut digest = PNG_CRC.digest();
+        digest.update(&chunk_type.bytes());
+        Ok(ChunkWriter {
+            writer: &mut self.writer,
+            chunk_type,
+            digest,
+            remaining: length,
+        })
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// The data of one chunk being written by `PngWriter::start_chunk`.
+pub struct ChunkWriter<'a, W: Write> {
+    writer: &'a mut W,
+    chunk_type: ChunkType,
+    digest: Digest<'static, u32>,
+    remaining: u32,
+}
+
+impl<W: Write> ChunkWriter<'_, W> {
+    /// Writes the CRC. Fails if fewer bytes were written than the declared length.
+    pub fn finish(self) -> Result<()> {
+        if self.remaining != 0 {
+            return Err(PngError::InvalidChunkData {
+                chunk_type: self.chunk_type,
+                reason: format!("{} bytes of data were never written", self.remaining),
+            });
+        }
+        self.writer
+            .write_all(&self.digest.finalize().to_be_bytes())?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ChunkWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.remaining as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "data is longer than the declared chunk length",
+            ));
+        }
+        let written = self.writer.write(buf)?;
+        self.digest.update(&buf[..written]);
+        self.remaining -= written as u32;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::ChunkReader;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![7; 100]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_write_chunks_matches_as_bytes() {
+        let png = testing_png();
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        for chunk in png.chunks() {
+            writer.write_chunk(chunk).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), png.as_bytes());
+    }
+
+    #[test]
+    fn test_write_data_computes_crc() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let data = b"This is where your secret message will be!";
+
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        writer.write_data(chunk_type, data).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let expected = Chunk::new(chunk_type, data.to_vec()).as_bytes();
+        assert_eq!(&bytes[8..], expected.as_slice());
+        assert_eq!(Chunk::try_from(&bytes[8..]).unwrap().crc(), 2882656334);
+    }
+
+    #[test]
+    fn test_chunk_writer_in_pieces() {
+        let chunk_type = ChunkType::from_str("IDAT").unwrap();
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        let mut chunk = writer.start_chunk(chunk_type, 100).unwrap();
+        for _ in 0..10 {
+            chunk.write_all(&[7; 10]).unwrap();
+        }
+        chunk.finish().unwrap();
+        let bytes = writer.finish().unwrap();
+
+        assert_eq!(
+            &bytes[8..],
+            Chunk::new(chunk_type, vec![7; 100]).as_bytes().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_chunk_writer_length_mismatch() {
+        let chunk_type = ChunkType::from_str("IDAT").unwrap();
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+
+        let mut chunk = writer.start_chunk(chunk_type, 4).unwrap();
+        assert!(chunk.write_all(&[0; 5]).is_err());
+
+        let mut chunk = writer.start_chunk(chunk_type, 4).unwrap();
+        chunk.write_all(&[0; 3]).unwrap();
+        assert!(chunk.finish().is_err());
+    }
+
+    #[test]
+    fn test_pipe_reader_into_writer() {
+        let png = testing_png();
+        let bytes = png.as_bytes();
+        let secret = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec());
+
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        for chunk in ChunkReader::new(bytes.as_slice()) {
+            let chunk = chunk.unwrap();
+            if chunk.chunk_type().to_string() == "IEND" {
+                writer.write_chunk(&secret).unwrap();
+            }
+            writer.write_chunk(&chunk).unwrap();
+        }
+        let output = writer.finish().unwrap();
+
+        let mut expected = testing_png();
+        expected.append_chunk(secret);
+        assert_eq!(output, expected.as_bytes());
+    }
+}
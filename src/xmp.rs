@@ -0,0 +1,436 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::chunk_data::invalid;
+use crate::error::{PngError, Result};
+use crate::png::Png;
+use crate::text::InternationalTextChunk;
+
+/// The `iTXt` keyword XMP packets are stored under.
+pub const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+
+/// A packet with a single empty `rdf:Description`, used when an image has none.
+const EMPTY_PACKET: &str = concat!(
+    "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n",
+    "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n",
+    " <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n",
+    "  <rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+    "  </rdf:Description>\n",
+    " </rdf:RDF>\n",
+    "</x:xmpmeta>\n",
+    "<?xpacket end=\"w\"?>",
+);
+
+/// The Dublin Core properties pngme can read and write.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DublinCore {
+    /// An ordered list (`rdf:Seq`) of authors.
+    Creator,
+    /// A language alternative (`rdf:Alt`) with the copyright statement.
+    Rights,
+    /// A language alternative (`rdf:Alt`) describing the image.
+    Description,
+}
+
+impl DublinCore {
+    fn element(self) -> &'static str {
+        match self {
+            DublinCore::Creator => "dc:creator",
+            DublinCore::Rights => "dc:rights",
+            DublinCore::Description => "dc:description",
+        }
+    }
+
+    fn container(self) -> &'static str {
+        match self {
+            DublinCore::Creator => "rdf:Seq",
+            DublinCore::Rights | DublinCore::Description => "rdf:Alt",
+        }
+    }
+}
+
+impl FromStr for DublinCore {
+    type Err = PngError;
+
+    /// Accepts `creator`, `rights` or `description`, with or without a `dc:` prefix.
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.strip_prefix("dc:").unwrap_or(s);
+        match name.to_ascii_lowercase().as_str() {
+            "creator" => Ok(DublinCore::Creator),
+            "rights" => Ok(DublinCore::Rights),
+            "description" => Ok(DublinCore::Description),
+            _ => Err(invalid(
+                InternationalTextChunk::chunk_type(),
+                format!("unknown Dublin Core property {:?}", s),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for DublinCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.element())
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(|code| code.ok())
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The index of the `>` closing the tag that starts at `start`, skipping
+/// over quoted attribute values.
+fn tag_end(xml: &str, start: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in xml[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(start + i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The start of the first `<name` tag at or after `from`.
+fn find_start_tag(xml: &str, name: &str, from: usize) -> Option<usize> {
+    let open = format!("<{}", name);
+    let mut at = from;
+    while let Some(i) = xml[at..].find(&open) {
+        let start = at + i;
+        match xml[start + open.len()..].chars().next() {
+            Some(c) if c.is_whitespace() || c == '>' || c == '/' => return Some(start),
+            _ => at = start + open.len(),
+        }
+    }
+    None
+}
+
+/// A located element: the whole element and the content between its tags.
+struct Element {
+    outer: Range<usize>,
+    start_tag: Range<usize>,
+    content: Range<usize>,
+}
+
+fn find_element(xml: &str, name: &str, from: usize) -> Result<Option<Element>> {
+    let Some(start) = find_start_tag(xml, name, from) else {
+        return Ok(None);
+    };
+    let unclosed = || {
+        invalid(
+            InternationalTextChunk::chunk_type(),
+            format!("XMP element <{}> is not closed", name),
+        )
+    };
+    let end = tag_end(xml, start).ok_or_else(unclosed)?;
+    if xml[..end].ends_with('/') {
+        return Ok(Some(Element {
+            outer: start..end + 1,
+            start_tag: start..end + 1,
+            content: end + 1..end + 1,
+        }));
+    }
+    let close = format!("</{}>", name);
+    let content_end = end + 1 + xml[end + 1..].find(&close).ok_or_else(unclosed)?;
+    Ok(Some(Element {
+        outer: start..content_end + close.len(),
+        start_tag: start..end + 1,
+        content: end + 1..content_end,
+    }))
+}
+
+/// An XMP packet: an RDF/XML document describing the image.
+///
+/// Only the properties in [`DublinCore`] are understood, and only when
+/// written with the conventional `dc:` and `rdf:` prefixes. Everything else in
+/// the packet is kept as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xmp {
+    pub xml: String,
+}
+
+impl Default for Xmp {
+    fn default() -> Self {
+        Xmp {
+            xml: EMPTY_PACKET.to_string(),
+        }
+    }
+}
+
+impl Xmp {
+    pub fn new(xml: &str) -> Xmp {
+        Xmp {
+            xml: xml.to_string(),
+        }
+    }
+
+    /// The value of `property`. Creators are joined with `"; "`; for language
+    /// alternatives the `x-default` entry is preferred.
+    pub fn property(&self, property: DublinCore) -> Result<Option<String>> {
+        let Some(element) = find_element(&self.xml, property.element(), 0)? else {
+            return Ok(None);
+        };
+        let mut items = Vec::new();
+        let mut at = element.content.start;
+        while let Some(item) = find_element(&self.xml, "rdf:li", at)? {
+            if item.outer.end > element.content.end {
+                break;
+            }
+            let start_tag = &self.xml[item.start_tag.clone()];
+            let is_default = start_tag.contains("xml:lang=\"x-default\"")
+                || start_tag.contains("xml:lang='x-default'");
+            items.push((is_default, unescape(&self.xml[item.content.clone()])));
+            at = item.outer.end;
+        }
+        if items.is_empty() {
+            let text = unescape(self.xml[element.content].trim());
+            return Ok(Some(text).filter(|text| !text.is_empty()));
+        }
+        let value = match property {
+            DublinCore::Creator => items
+                .into_iter()
+                .map(|(_, item)| item)
+                .collect::<Vec<_>>()
+                .join("; "),
+            _ => {
+                let default = items.iter().position(|(is_default, _)| *is_default);
+                items.swap_remove(default.unwrap_or(0)).1
+            }
+        };
+        Ok(Some(value))
+    }
+
+    /// Sets `property` to a single value, replacing any existing value. The
+    /// property is added to the first `rdf:Description` if missing.
+    pub fn set_property(&mut self, property: DublinCore, value: &str) -> Result<()> {
+        let item = match property {
+            DublinCore::Creator => format!("<rdf:li>{}</rdf:li>", escape(value)),
+            _ => format!("<rdf:li xml:lang=\"x-default\">{}</rdf:li>", escape(value)),
+        };
+        let new = format!(
+            "<{name}><{container}>{item}</{container}></{name}>",
+            name = property.element(),
+            container = property.container(),
+        );
+
+        if let Some(element) = find_element(&self.xml, property.element(), 0)? {
+            self.xml.replace_range(element.outer, &new);
+            return Ok(());
+        }
+
+        let description = find_element(&self.xml, "rdf:Description", 0)?.ok_or_else(|| {
+            invalid(
+                InternationalTextChunk::chunk_type(),
+                "XMP packet has no rdf:Description".to_string(),
+            )
+        })?;
+        let tag = description.start_tag;
+        let namespace = if self.xml.contains("xmlns:dc=") {
+            String::new()
+        } else {
+            format!(" xmlns:dc=\"{}\"", DC_NAMESPACE)
+        };
+        if description.content.is_empty() && self.xml[..tag.end].ends_with("/>") {
+            // Expand <rdf:Description .../> so it can hold the property.
+            let attributes = self.xml[tag.start..tag.end - 2].trim_end().to_string();
+            self.xml.replace_range(
+                tag,
+                &format!("{}{}>{}</rdf:Description>", attributes, namespace, new),
+            );
+        } else {
+            self.xml.insert_str(tag.end, &new);
+            self.xml.insert_str(tag.end - 1, &namespace);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Xmp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.xml)
+    }
+}
+
+impl Png {
+    /// The XMP packet stored in the `iTXt` chunk with keyword
+    /// `XML:com.adobe.xmp`, if there is one.
+    pub fn xmp(&self) -> Result<Option<String>> {
+        Ok(self
+            .text(XMP_KEYWORD)?
+            .map(|entry| entry.text().to_string()))
+    }
+
+    /// Replaces the XMP packet. It is stored uncompressed, as the XMP spec
+    /// recommends so tools that scan for packets can find it.
+    pub fn set_xmp(&mut self, xml: &str) -> Result<()> {
+        let entry = InternationalTextChunk::new(XMP_KEYWORD, xml)?;
+        self.set_text(&entry.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+
+    const PACKET: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/">
+   <dc:creator>
+    <rdf:Seq>
+     <rdf:li>Zoë Müller</rdf:li>
+     <rdf:li>Tom &amp; Jerry</rdf:li>
+    </rdf:Seq>
+   </dc:creator>
+   <dc:rights>
+    <rdf:Alt>
+     <rdf:li xml:lang="fr">Tous droits réservés</rdf:li>
+     <rdf:li xml:lang="x-default">&#xA9; 2024 PNGMe</rdf:li>
+    </rdf:Alt>
+   </dc:rights>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test]
+    fn test_property() {
+        let xmp = Xmp::new(PACKET);
+        assert_eq!(
+            xmp.property(DublinCore::Creator).unwrap().unwrap(),
+            "Zoë Müller; Tom & Jerry"
+        );
+        assert_eq!(
+            xmp.property(DublinCore::Rights).unwrap().unwrap(),
+            "© 2024 PNGMe"
+        );
+        assert_eq!(xmp.property(DublinCore::Description).unwrap(), None);
+    }
+
+    #[test]
+    fn test_set_property() {
+        let mut xmp = Xmp::new(PACKET);
+        xmp.set_property(DublinCore::Rights, "CC BY <4.0>").unwrap();
+        xmp.set_property(DublinCore::Description, "Two dice")
+            .unwrap();
+        assert_eq!(
+            xmp.property(DublinCore::Rights).unwrap().unwrap(),
+            "CC BY <4.0>"
+        );
+        assert_eq!(
+            xmp.property(DublinCore::Description).unwrap().unwrap(),
+            "Two dice"
+        );
+        assert!(xmp.xml.contains("CC BY &lt;4.0&gt;"));
+        assert!(!xmp.xml.contains("Tous droits"));
+        assert_eq!(xmp.xml.matches("xmlns:dc=").count(), 1);
+        assert!(xmp.xml.contains("Tom &amp; Jerry"));
+    }
+
+    #[test]
+    fn test_set_property_on_empty_description() {
+        let mut xmp = Xmp::new(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about=""/></rdf:RDF></x:xmpmeta>"#,
+        );
+        xmp.set_property(DublinCore::Creator, "Ferris").unwrap();
+        assert_eq!(
+            xmp.xml,
+            concat!(
+                r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">"#,
+                r#"<rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/">"#,
+                r#"<dc:creator><rdf:Seq><rdf:li>Ferris</rdf:li></rdf:Seq></dc:creator>"#,
+                r#"</rdf:Description></rdf:RDF></x:xmpmeta>"#
+            )
+        );
+
+        let mut xmp = Xmp::default();
+        xmp.set_property(DublinCore::Rights, "Public domain")
+            .unwrap();
+        assert_eq!(
+            xmp.property(DublinCore::Rights).unwrap().unwrap(),
+            "Public domain"
+        );
+
+        let mut xmp = Xmp::new("<x:xmpmeta/>");
+        assert!(xmp.set_property(DublinCore::Rights, "x").is_err());
+        let xmp = Xmp::new("<dc:rights><rdf:Alt>");
+        assert!(xmp.property(DublinCore::Rights).is_err());
+    }
+
+    #[test]
+    fn test_dublin_core_from_str() {
+        assert_eq!(
+            DublinCore::from_str("dc:rights").unwrap(),
+            DublinCore::Rights
+        );
+        assert_eq!(
+            DublinCore::from_str("Creator").unwrap(),
+            DublinCore::Creator
+        );
+        assert!(DublinCore::from_str("title").is_err());
+        assert_eq!(DublinCore::Description.to_string(), "dc:description");
+    }
+
+    #[test]
+    fn test_png_xmp() {
+        let mut png = Png::from_chunks(vec![Chunk::new(
+            ChunkType::from_str("IEND").unwrap(),
+            Vec::new(),
+        )]);
+        assert_eq!(png.xmp().unwrap(), None);
+        png.set_xmp("<old/>").unwrap();
+        png.set_xmp(PACKET).unwrap();
+        assert_eq!(png.xmp().unwrap().unwrap(), PACKET);
+        assert_eq!(png.chunks().len(), 2);
+        assert_eq!(
+            png.chunks()[0].chunk_type(),
+            InternationalTextChunk::chunk_type()
+        );
+    }
+}
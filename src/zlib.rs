@@ -0,0 +1,265 @@
+use std::io::{Read, Write};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Decompress, FlushDecompress, Status};
+
+use crate::chunk_type::ChunkType;
+use crate::error::{PngError, Result};
+
+/// The only compression method PNG defines: zlib deflate.
+pub const COMPRESSION_METHOD_ZLIB: u8 = 0;
+
+/// Checks the compression method byte of a compressed chunk.
+pub(crate) fn check_method(chunk_type: ChunkType, method: u8) -> Result<()> {
+    if method != COMPRESSION_METHOD_ZLIB {
+        return Err(PngError::UnknownCompressionMethod { chunk_type, method });
+    }
+    Ok(())
+}
+
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing into a Vec cannot fail.
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// The most bytes a single compressed text, profile or metadata chunk may
+/// inflate to, so a small chunk cannot expand into gigabytes.
+pub const MAX_DECOMPRESSED_LEN: usize = 16 << 20;
+
+/// Inflates a zlib stream, reporting corrupt data against `chunk_type`.
+pub(crate) fn decompress(chunk_type: ChunkType, data: &[u8]) -> Result<Vec<u8>> {
+    decompress_limited(chunk_type, data, MAX_DECOMPRESSED_LEN)
+}
+
+/// Inflates at most `limit` bytes, failing with `DecompressedTooLarge` beyond it.
+fn decompress_limited(chunk_type: ChunkType, data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(data)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| PngError::InvalidChunkData {
+            chunk_type,
+            reason: format!("corrupt zlib stream: {}", e),
+        })?;
+    if out.len() > limit {
+        return Err(PngError::DecompressedTooLarge { chunk_type, limit });
+    }
+    Ok(out)
+}
+
+/// The image data buffer starts at this many times the compressed size...
+const INITIAL_INFLATE_RATIO: usize = 4;
+/// ...but no larger than this, however large the stream.
+const MAX_INITIAL_INFLATE_CAPACITY: usize = 16 << 20;
+/// The smallest step the buffer grows by once full.
+const MIN_INFLATE_GROWTH: usize = 64 << 10;
+
+/// The Adler-32 checksum zlib stores after the deflate data.
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65_521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the most bytes that can be summed before `b` could overflow.
+    for block in data.chunks(5552) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+/// Inflates the zlib stream of the image data, checking the header, that the
+/// deflate data ends, and the Adler-32 trailer. Inflating stops with an error
+/// once more than `expected_len` bytes come out. Bytes after the trailer are
+/// ignored, as decoders do.
+pub(crate) fn inflate_image_data(stream: &[u8], expected_len: usize) -> Result<Vec<u8>> {
+    let corrupt = |reason: String| PngError::CorruptImageData(reason);
+    let (cmf, flg) = match stream {
+        [cmf, flg, ..] => (*cmf, *flg),
+        _ => return Err(corrupt("missing zlib header".to_string())),
+    };
+    if cmf & 0x0f != 8 || cmf >> 4 > 7 {
+        return Err(corrupt(format!(
+            "unsupported zlib method byte {:#04x}",
+            cmf
+        )));
+    }
+    if !(u16::from_be_bytes([cmf, flg])).is_multiple_of(31) {
+        return Err(corrupt("zlib header check bits are wrong".to_string()));
+    }
+    if flg & 0x20 != 0 {
+        return Err(corrupt(
+            "zlib preset dictionaries are not allowed".to_string(),
+        ));
+    }
+
+    let deflate = &stream[2..];
+    let mut inflater = Decompress::new(false);
+    // The header's size is only trusted as an upper bound: the buffer starts
+    // at a size tied to the compressed input and grows as data inflates, up
+    // to one spare byte so overlong data is noticed without inflating it all.
+    let limit = expected_len.saturating_add(1);
+    let initial = stream
+        .len()
+        .saturating_mul(INITIAL_INFLATE_RATIO)
+        .min(MAX_INITIAL_INFLATE_CAPACITY);
+    let mut out = Vec::with_capacity(initial.min(limit));
+    loop {
+        let consumed = inflater.total_in() as usize;
+        let produced = out.len();
+        let status = inflater
+            .decompress_vec(&deflate[consumed..], &mut out, FlushDecompress::None)
+            .map_err(|e| corrupt(format!("invalid deflate data: {}", e)))?;
+        if out.len() > expected_len {
+            return Err(corrupt(format!(
+                "more than the {} bytes of scanlines the header describes",
+                expected_len
+            )));
+        }
+        match status {
+            Status::StreamEnd => break,
+            _ if out.len() == out.capacity() => {
+                let grow = out.len().max(MIN_INFLATE_GROWTH).min(limit - out.len());
+                out.reserve_exact(grow);
+            }
+            _ if inflater.total_in() as usize == consumed && out.len() == produced => {
+                return Err(corrupt("zlib stream is truncated".to_string()));
+            }
+            _ => {}
+        }
+    }
+    if out.len() < expected_len {
+        return Err(corrupt(format!(
+            "expected {} bytes of scanlines, found {}",
+            expected_len,
+            out.len()
+        )));
+    }
+
+    let end = inflater.total_in() as usize;
+    let trailer = deflate
+        .get(end..end + 4)
+        .ok_or_else(|| corrupt("missing Adler-32 checksum".to_string()))?;
+    let expected = u32::from_be_bytes(trailer.try_into().unwrap());
+    let actual = adler32(&out);
+    if expected != actual {
+        return Err(PngError::Adler32Mismatch { expected, actual });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_round_trip() {
+        let data = b"hello hello hello hello hello".repeat(10);
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        let chunk_type = ChunkType::from_str("zTXt").unwrap();
+        assert_eq!(decompress(chunk_type, &compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn test_decompress_limit() {
+        let chunk_type = ChunkType::from_str("zTXt").unwrap();
+        let data = vec![b'a'; 1000];
+        let compressed = compress(&data);
+        assert_eq!(
+            decompress_limited(chunk_type, &compressed, 1000).unwrap(),
+            data
+        );
+        assert!(matches!(
+            decompress_limited(chunk_type, &compressed, 999),
+            Err(PngError::DecompressedTooLarge { limit: 999, .. })
+        ));
+
+        let bomb = compress(&vec![0; MAX_DECOMPRESSED_LEN + 1]);
+        assert!(bomb.len() < 64 << 10);
+        assert!(matches!(
+            decompress(chunk_type, &bomb),
+            Err(PngError::DecompressedTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        let data = vec![0xff; 100_000];
+        let compressed = compress(&data);
+        let trailer = &compressed[compressed.len() - 4..];
+        assert_eq!(adler32(&data).to_be_bytes(), trailer);
+    }
+
+    #[test]
+    fn test_inflate_image_data() {
+        let data = b"scanline data ".repeat(100);
+        let stream = compress(&data);
+        assert_eq!(inflate_image_data(&stream, data.len()).unwrap(), data);
+
+        let mut trailing = stream.clone();
+        trailing.extend_from_slice(b"junk");
+        assert!(inflate_image_data(&trailing, data.len()).is_ok());
+
+        let mut bad_checksum = stream.clone();
+        *bad_checksum.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            inflate_image_data(&bad_checksum, data.len()),
+            Err(PngError::Adler32Mismatch { .. })
+        ));
+
+        for (stream, expected_len) in [
+            (&stream[..1], data.len()),
+            (&b"\x78\x9d"[..], data.len()),
+            (&stream[..stream.len() - 2], data.len()),
+            (&stream[..stream.len() / 2], data.len()),
+            (&stream[..], data.len() + 1),
+            (&stream[..], data.len() - 1),
+        ] {
+            assert!(
+                matches!(
+                    inflate_image_data(stream, expected_len),
+                    Err(PngError::CorruptImageData(_))
+                ),
+                "{:?}",
+                inflate_image_data(stream, expected_len)
+            );
+        }
+    }
+
+    #[test]
+    fn test_inflate_image_data_ignores_declared_size() {
+        // A header claiming 80 GB must not be allocated up front.
+        let stream = compress(&[0; 1000]);
+        assert!(matches!(
+            inflate_image_data(&stream, 80_000_100_000),
+            Err(PngError::CorruptImageData(_))
+        ));
+        let data = vec![7; 1 << 20];
+        assert_eq!(
+            inflate_image_data(&compress(&data), data.len()).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let chunk_type = ChunkType::from_str("zTXt").unwrap();
+        assert!(matches!(
+            decompress(chunk_type, b"not zlib"),
+            Err(PngError::InvalidChunkData { .. })
+        ));
+        assert!(matches!(
+            check_method(chunk_type, 1),
+            Err(PngError::UnknownCompressionMethod { method: 1, .. })
+        ));
+    }
+}